@@ -1,4 +1,5 @@
 import { NextResponse } from "next/server";
+import { xdr } from "stellar-sdk";
 import { buildContractTransaction } from "@/lib/stellar/contract-transactions";
 import { getStellarNetworkConfig } from "@/lib/stellar/network";
 
@@ -20,7 +21,8 @@ export async function POST(req: Request) {
       sourcePublicKey,
       contractId,
       method,
-      args,
+      // Arguments arrive as base64 XDR ScVals, which keeps their contract types.
+      args: (args ?? []).map((arg: string) => xdr.ScVal.fromXDR(arg, "base64")),
       network: networkConfig.name,
     });
 
@@ -5,6 +5,7 @@ import { useParams, useRouter } from "next/navigation";
 import { useWallet } from "@/hooks/useWallet";
 import { Loader2, AlertCircle, CheckCircle2, Wallet, ArrowRight, ExternalLink } from "lucide-react";
 import Link from "next/link";
+import { toBaseUnits } from "@/lib/stellar/amounts";
 
 // ---------------------------------------------------------------------------
 // Mock Data
@@ -17,6 +18,7 @@ const MOCK_LISTING = {
   rentAmount: 3500, // Total rent in XLM
   contractAddress: "CDLZFC3SYJYDZT7KQLSPR4VP68SHJLHPRJ3D547PXVPH3SA2PTUXWW5N",
   shareAmount: 1750, // Tenant's share in XLM
+  tokenDecimals: 7, // Decimals of the escrow's token contract
   dueDate: "2023-11-01",
   status: "pending",
   landlord: "GBDWB...",
@@ -127,14 +129,21 @@ export default function PaymentPage() {
         const { getStellarNetworkConfig } = await import("@/lib/stellar/network");
         const networkConfig = getStellarNetworkConfig();
         const { getFreighterPublicKey } = await import("@/lib/stellar/freighter");
+        const { Address, ScInt } = await import("stellar-sdk");
+        const publicKey = walletKey || await getFreighterPublicKey();
+        // deposit(tenant: Address, amount: i128), sent as XDR so the types survive JSON
+        const args = [
+          new Address(publicKey).toScVal(),
+          new ScInt(toBaseUnits(listing.shareAmount, listing.tokenDecimals)).toI128(),
+        ].map((arg) => arg.toXDR("base64"));
         const res = await fetch("/api/payments/estimate-fee", {
           method: "POST",
           headers: { "Content-Type": "application/json" },
           body: JSON.stringify({
             contractId: listing.contractAddress,
             method: "deposit",
-            args: [BigInt(listing.shareAmount * 10_000_000).toString()],
-            sourcePublicKey: walletKey || await getFreighterPublicKey(),
+            args,
+            sourcePublicKey: publicKey,
             network: networkConfig.name,
           }),
         });
@@ -174,7 +183,7 @@ export default function PaymentPage() {
       // 1. Call Smart Contract
       const result = await RentContractService.deposit(
         listing.contractAddress,
-        BigInt(listing.shareAmount * 10_000_000), // Convert to stroops (assuming 7 decimals)
+        toBaseUnits(listing.shareAmount, listing.tokenDecimals),
         publicKey,
         networkConfig.networkPassphrase,
         networkConfig.sorobanRpcUrl
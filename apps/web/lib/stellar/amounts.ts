@@ -0,0 +1,9 @@
+/**
+ * Converts a display amount into the smallest unit of a token with the given
+ * `decimals`, as the escrow expects: 7 for XLM and other Stellar assets,
+ * whatever the token contract reports otherwise.
+ */
+export function toBaseUnits(amount: number, decimals: number): bigint {
+  const [whole, fraction = ""] = amount.toFixed(decimals).split(".");
+  return BigInt(whole + fraction);
+}
@@ -24,6 +24,17 @@ export enum ContractStatus {
   CANCELLED = 4
 }
 
+/**
+ * Maps the contract's `DisputeStatus` variants to the `dispute_status` values
+ * stored in the disputes table.
+ */
+export const DISPUTE_STATUS_FROM_CONTRACT = {
+  Open: "pending",
+  Resolved: "resolved",
+  Rejected: "rejected",
+  Refunded: "refunded",
+} as const;
+
 /**
  * RentContractService handles interaction with the Soroban Rent Escrow contract.
  * It provides a high-level API for common operations like deployment, deposits, and status checks.
@@ -154,17 +165,39 @@ export class RentContractService {
   }
 
   /**
-   * Checks the status of the contract (e.g. 0: Pending, 1: Ready, 2: Completed).
+   * Reads the on-chain status of the contract via its `get_status` view.
    * 
    * @param contractId The target contract ID.
-   * @returns A status indicator (string or number depending on contract design).
+   * @returns The current ContractStatus of the escrow.
    */
   async getContractStatus(contractId: string): Promise<ContractStatus> {
     this.log(`getContractStatus: contract=${contractId}`);
 
     return this.withRetry(async () => {
-      // Mocked status retrieval
-      return ContractStatus.ACTIVE;
+      try {
+        const contract = new Contract(contractId);
+        // We use a dummy account to simulate the read-only call
+        const dummyAccount = new Keypair().account();
+
+        const tx = new TransactionBuilder(dummyAccount, { 
+          fee: BASE_FEE, 
+          networkPassphrase: this.networkConfig.networkPassphrase 
+        })
+          .addOperation(contract.call("get_status"))
+          .setTimeout(30)
+          .build();
+
+        const simulation = await this.server.simulateTransaction(tx);
+
+        if (SorobanRpc.Api.isSimulationSuccess(simulation)) {
+          return Number(scValToNative(simulation.result!.retval)) as ContractStatus;
+        }
+
+        throw new Error("Contract call simulation failed.");
+      } catch (error) {
+        this.log(`Error fetching status: ${error}`);
+        throw error;
+      }
     });
   }
 
@@ -184,6 +217,18 @@ export class RentContractService {
     if (message.includes("Error(Contract, #2)")) return "Insufficient funds: The contract does not have enough XLM.";
     if (message.includes("Error(Contract, #3)")) return "Condition not met: Withdrawal is only available when full rent is paid.";
     if (message.includes("Error(Contract, #4)")) return "Invalid tenant: This address is not part of the agreement.";
+    if (message.includes("Error(Contract, #5)")) return "Already initialized: This agreement has already been set up.";
+    if (message.includes("Error(Contract, #6)")) return "Invalid state: The agreement does not allow this action in its current status.";
+    if (message.includes("Error(Contract, #7)")) return "Overflow: The amount is too large.";
+    if (message.includes("Error(Contract, #8)")) return "Expired: The rent deadline for this agreement has passed.";
+    if (message.includes("Error(Contract, #9)")) return "Not initialized: This agreement has not been set up yet.";
+    if (message.includes("Error(Contract, #10)")) return "Invalid amount: The amount must be greater than zero.";
+    if (message.includes("Error(Contract, #11)")) return "Invalid split: The tenant shares must add up to the full rent.";
+    if (message.includes("Error(Contract, #12)")) return "Invalid schedule: The rent schedule needs a non-zero interval, at least one period and a non-zero contest window, and an installment plan must cover the whole share with installments due in order within one period.";
+    if (message.includes("Error(Contract, #13)")) return "Invalid period: That period is not part of this agreement's schedule.";
+    if (message.includes("Error(Contract, #14)")) return "Disputed: These funds are frozen until the open dispute is resolved.";
+    if (message.includes("Error(Contract, #15)")) return "Invalid arbitration: The arbiter panel must be independent of the agreement and able to reach its threshold.";
+    if (message.includes("Error(Contract, #16)")) return "Stale price: The oracle has no recent price for the rent token.";
     
     return "The contract operation failed. Please check your balance and try again.";
   }
@@ -4,14 +4,14 @@ import {
   submitSignedContractTransaction,
   type SubmittedTransaction,
 } from "@/lib/stellar/contract-transactions";
-import { ScInt, xdr } from "stellar-sdk";
+import { Address, ScInt, xdr } from "stellar-sdk";
 
 export const RentContractService = {
   /**
    * Calls the 'deposit' function on the rent contract.
    * @param contractId The Contract ID (C...)
    * @param amount The amount to deposit (in stroops/minimal unit, as i128)
-   * @param sourcePublicKey The wallet address of the user
+   * @param sourcePublicKey The wallet address of the user, who deposits as the tenant
    * @param networkPassphrase The network passphrase (e.g. Testnet)
    * @param rpcUrl The Soroban RPC URL
    */
@@ -22,7 +22,8 @@ export const RentContractService = {
     networkPassphrase?: string,
     rpcUrl?: string
   ): Promise<SubmittedTransaction> {
-    // Convert amount to i128 ScVal
+    // deposit(tenant: Address, amount: i128)
+    const tenantScVal = new Address(sourcePublicKey).toScVal();
     const amountScVal = new ScInt(amount).toI128();
 
     // 1. Build the transaction
@@ -30,7 +31,7 @@ export const RentContractService = {
       sourcePublicKey,
       contractId,
       method: "deposit",
-      args: [amountScVal],
+      args: [tenantScVal, amountScVal],
       networkPassphrase,
       rpcUrl,
     });
@@ -51,4 +52,42 @@ export const RentContractService = {
 
     return result;
   },
+
+  /**
+   * Calls the 'claim_refund' function on the rent contract, returning what
+   * the tenant deposited for a period that expired before it was funded.
+   * @param contractId The Contract ID (C...)
+   * @param sourcePublicKey The wallet address of the user, who claims as the tenant
+   * @param networkPassphrase The network passphrase (e.g. Testnet)
+   * @param rpcUrl The Soroban RPC URL
+   */
+  async claimRefund(
+    contractId: string,
+    sourcePublicKey: string,
+    networkPassphrase?: string,
+    rpcUrl?: string
+  ): Promise<SubmittedTransaction> {
+    // claim_refund(tenant: Address)
+    const tenantScVal = new Address(sourcePublicKey).toScVal();
+
+    const build = await buildContractTransaction({
+      sourcePublicKey,
+      contractId,
+      method: "claim_refund",
+      args: [tenantScVal],
+      networkPassphrase,
+      rpcUrl,
+    });
+
+    const signedXdr = await signContractTransaction(
+      build.unsignedXdr,
+      build.networkPassphrase
+    );
+
+    return submitSignedContractTransaction(
+      signedXdr,
+      build.networkPassphrase,
+      build.rpcUrl
+    );
+  },
 };
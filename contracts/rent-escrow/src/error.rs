@@ -0,0 +1,48 @@
+use soroban_sdk::contracterror;
+
+/// Failures reported by the escrow. Codes 1-4 are relied on by
+/// `parseContractError` in the web app and must stay stable.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// The caller is not allowed to perform this action.
+    Unauthorized = 1,
+    /// The payer does not hold enough tokens.
+    InsufficientFunds = 2,
+    /// The escrow is not in a position to perform this action yet.
+    ConditionNotMet = 3,
+    /// The address is not a tenant of the agreement, or the tenant list is
+    /// empty, too long or malformed.
+    InvalidTenant = 4,
+    /// `initialize` has already been called.
+    AlreadyInitialized = 5,
+    /// The escrow's status does not allow this action.
+    InvalidState = 6,
+    /// An amount would overflow.
+    Overflow = 7,
+    /// The escrow has expired.
+    Expired = 8,
+    /// `initialize` has not been called yet.
+    NotInitialized = 9,
+    /// An amount is zero or negative.
+    InvalidAmount = 10,
+    /// The rent split does not match the tenants or does not add up to the
+    /// rent.
+    InvalidSplit = 11,
+    /// The schedule has no periods, a zero interval, or ends beyond the
+    /// representable time range, the contest window is zero, or an
+    /// installment plan does not lay out the whole share within a period.
+    InvalidSchedule = 12,
+    /// The period is outside the lease.
+    InvalidPeriod = 13,
+    /// The funds involved are frozen by an open dispute.
+    Disputed = 14,
+    /// The arbiter panel is empty, includes a party to the agreement or the
+    /// same arbiter twice, its threshold cannot be met, or it has no time to
+    /// rule.
+    InvalidArbitration = 15,
+    /// The oracle has no price for the token, or only one older than the
+    /// agreement allows.
+    StalePrice = 16,
+}
@@ -0,0 +1,318 @@
+//! Events published by the escrow.
+//!
+//! Every event has three topics: its name, the [`EVENT_VERSION`] of its
+//! payload, and the address it concerns — the tenant for deposits, late fees
+//! and anything returned to them, the landlord for the agreement and its
+//! payouts, the opener for disputes, the arbiter for votes, and the escrow
+//! itself for status changes. An indexer can follow a party by filtering on
+//! the third topic alone, and rebuild the payment history of every period
+//! from the event data.
+//!
+//! The data of each event is one of the structs below. Any change to their
+//! fields bumps [`EVENT_VERSION`], so an indexer can tell which layout it is
+//! reading instead of misreading a newer one.
+
+use soroban_sdk::{contracttype, Address, BytesN, Env, IntoVal, Symbol, Val, Vec};
+
+use crate::types::{ContractStatus, DisputeStatus, Outcome};
+
+/// Version of the event payloads, published as the second topic of every
+/// event.
+pub const EVENT_VERSION: u32 = 1;
+
+/// Data of the `initialized` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InitializedEvent {
+    pub tenants: Vec<Address>,
+    pub rent_amount: i128,
+    pub token: Address,
+}
+
+/// Data of the `deposited` event: `rent` went to the tenant's share of
+/// `period` and `credit` was kept for them.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositedEvent {
+    pub period: u32,
+    pub rent: i128,
+    pub credit: i128,
+}
+
+/// Data of the `credit_applied` event: `rent` of the tenant's credit went to
+/// their share of `period` and `credit` is left over.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreditAppliedEvent {
+    pub period: u32,
+    pub rent: i128,
+    pub credit: i128,
+}
+
+/// Data of the `credit_withdrawn` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreditWithdrawnEvent {
+    pub amount: i128,
+}
+
+/// Data of the `late_fee` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LateFeeEvent {
+    pub period: u32,
+    pub fee: i128,
+}
+
+/// Data of the `disbursed` event: rent and late fees paid to the landlord.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisbursedEvent {
+    pub period: u32,
+    pub amount: i128,
+}
+
+/// Data of the `refunded` event: what the tenant got back for `period`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RefundedEvent {
+    pub period: u32,
+    pub amount: i128,
+}
+
+/// Data of the `security_funded` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecurityFundedEvent {
+    pub amount: i128,
+}
+
+/// Data of the `security_refunded` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecurityRefundedEvent {
+    pub amount: i128,
+}
+
+/// Data of the `deductions_proposed`, `deductions_contested` and
+/// `deductions_vote_cast` events.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeductionsEvent {
+    pub total: i128,
+}
+
+/// Data of the `security_settled` event: `deducted` went to the landlord and
+/// `refunded` back to the tenants.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecuritySettledEvent {
+    pub deducted: i128,
+    pub refunded: i128,
+}
+
+/// Data of the `dispute_opened` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputeOpenedEvent {
+    pub period: u32,
+    pub evidence: BytesN<32>,
+}
+
+/// Data of the `vote_cast` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VoteCastEvent {
+    pub period: u32,
+    pub outcome: Outcome,
+}
+
+/// Data of the `dispute_resolved` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisputeResolvedEvent {
+    pub period: u32,
+    pub status: DisputeStatus,
+    pub to_landlord: i128,
+    pub to_tenants: i128,
+}
+
+/// Data of the `status_changed` event.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusChangedEvent {
+    pub from: ContractStatus,
+    pub to: ContractStatus,
+}
+
+fn publish<D: IntoVal<Env, Val>>(env: &Env, name: &str, subject: &Address, data: D) {
+    env.events().publish(
+        (Symbol::new(env, name), EVENT_VERSION, subject.clone()),
+        data,
+    );
+}
+
+/// The agreement was set up.
+pub fn initialized(
+    env: &Env,
+    landlord: &Address,
+    tenants: &Vec<Address>,
+    rent_amount: i128,
+    token: &Address,
+) {
+    let data = InitializedEvent {
+        tenants: tenants.clone(),
+        rent_amount,
+        token: token.clone(),
+    };
+    publish(env, "initialized", landlord, data);
+}
+
+/// `tenant` deposited towards `period`. Late fees are reported by
+/// [`late_fee`].
+pub fn deposited(env: &Env, tenant: &Address, period: u32, rent: i128, credit: i128) {
+    let data = DepositedEvent {
+        period,
+        rent,
+        credit,
+    };
+    publish(env, "deposited", tenant, data);
+}
+
+/// `tenant`'s credit was put towards `period` when collection moved on to it.
+pub fn credit_applied(env: &Env, tenant: &Address, period: u32, rent: i128, credit: i128) {
+    let data = CreditAppliedEvent {
+        period,
+        rent,
+        credit,
+    };
+    publish(env, "credit_applied", tenant, data);
+}
+
+/// `tenant` took back their unused credit.
+pub fn credit_withdrawn(env: &Env, tenant: &Address, amount: i128) {
+    publish(
+        env,
+        "credit_withdrawn",
+        tenant,
+        CreditWithdrawnEvent { amount },
+    );
+}
+
+/// `tenant` paid `fee` in late fees for `period`.
+pub fn late_fee(env: &Env, tenant: &Address, period: u32, fee: i128) {
+    publish(env, "late_fee", tenant, LateFeeEvent { period, fee });
+}
+
+/// The rent and late fees of `period` were paid out to the landlord.
+pub fn disbursed(env: &Env, landlord: &Address, period: u32, amount: i128) {
+    publish(
+        env,
+        "disbursed",
+        landlord,
+        DisbursedEvent { period, amount },
+    );
+}
+
+/// What `tenant` deposited towards `period` was returned to them.
+pub fn refunded(env: &Env, tenant: &Address, period: u32, amount: i128) {
+    publish(env, "refunded", tenant, RefundedEvent { period, amount });
+}
+
+/// `tenant` put up their part of the security deposit.
+pub fn security_funded(env: &Env, tenant: &Address, amount: i128) {
+    publish(
+        env,
+        "security_funded",
+        tenant,
+        SecurityFundedEvent { amount },
+    );
+}
+
+/// `tenant` got back `amount` of their part of the security deposit.
+pub fn security_refunded(env: &Env, tenant: &Address, amount: i128) {
+    publish(
+        env,
+        "security_refunded",
+        tenant,
+        SecurityRefundedEvent { amount },
+    );
+}
+
+/// The landlord proposed deductions from the security deposit.
+pub fn deductions_proposed(env: &Env, landlord: &Address, total: i128) {
+    publish(
+        env,
+        "deductions_proposed",
+        landlord,
+        DeductionsEvent { total },
+    );
+}
+
+/// `tenant` contested the proposed deductions.
+pub fn deductions_contested(env: &Env, tenant: &Address, total: i128) {
+    publish(
+        env,
+        "deductions_contested",
+        tenant,
+        DeductionsEvent { total },
+    );
+}
+
+/// `arbiter` voted to allow `total` of the contested deductions.
+pub fn deductions_vote_cast(env: &Env, arbiter: &Address, total: i128) {
+    publish(
+        env,
+        "deductions_vote_cast",
+        arbiter,
+        DeductionsEvent { total },
+    );
+}
+
+/// The security deposit was settled.
+pub fn security_settled(env: &Env, landlord: &Address, deducted: i128, refunded: i128) {
+    let data = SecuritySettledEvent { deducted, refunded };
+    publish(env, "security_settled", landlord, data);
+}
+
+/// `opened_by` opened a dispute over `period`.
+pub fn dispute_opened(env: &Env, opened_by: &Address, period: u32, evidence: &BytesN<32>) {
+    let data = DisputeOpenedEvent {
+        period,
+        evidence: evidence.clone(),
+    };
+    publish(env, "dispute_opened", opened_by, data);
+}
+
+/// `arbiter` voted for `outcome` on the dispute over `period`.
+pub fn vote_cast(env: &Env, arbiter: &Address, period: u32, outcome: &Outcome) {
+    let data = VoteCastEvent {
+        period,
+        outcome: outcome.clone(),
+    };
+    publish(env, "vote_cast", arbiter, data);
+}
+
+/// The dispute `opened_by` opened over `period` was resolved.
+pub fn dispute_resolved(
+    env: &Env,
+    opened_by: &Address,
+    period: u32,
+    status: DisputeStatus,
+    to_landlord: i128,
+    to_tenants: i128,
+) {
+    let data = DisputeResolvedEvent {
+        period,
+        status,
+        to_landlord,
+        to_tenants,
+    };
+    publish(env, "dispute_resolved", opened_by, data);
+}
+
+/// The escrow moved from `from` to `to`.
+pub fn status_changed(env: &Env, from: ContractStatus, to: ContractStatus) {
+    let data = StatusChangedEvent { from, to };
+    publish(env, "status_changed", &env.current_contract_address(), data);
+}
@@ -1,14 +1,1278 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, symbol_short, Env, Symbol};
+use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env, Map, Vec};
+
+mod error;
+mod events;
+mod oracle;
+mod storage;
+mod types;
+
+pub use error::Error;
+pub use events::{
+    CreditAppliedEvent, CreditWithdrawnEvent, DeductionsEvent, DepositedEvent, DisbursedEvent,
+    DisputeOpenedEvent, DisputeResolvedEvent, InitializedEvent, LateFeeEvent, RefundedEvent,
+    SecurityFundedEvent, SecurityRefundedEvent, SecuritySettledEvent, StatusChangedEvent,
+    VoteCastEvent, EVENT_VERSION,
+};
+pub use oracle::{Oracle, OracleClient, PriceData};
+pub use types::{
+    Agreement, Arbitration, ContractStatus, DailyFee, Deduction, Dispute, DisputeStatus, Fallback,
+    Installment, LateFee, LateFeePolicy, Outcome, Payment, PayoutMode, Period, PeriodRecord,
+    PeriodStatus, PriceFeed, Pricing, Schedule, SecurityDeposit, Settlement, SettlementStatus,
+    Split, Terms, MAX_HISTORY_READS, MAX_TENANTS,
+};
 
 #[contract]
 pub struct RentContract;
 
 #[contractimpl]
 impl RentContract {
-    pub fn hello(_env: Env, _to: Symbol) -> Symbol {
-        symbol_short!("Hello")
+    /// Binds the escrow to a rent agreement between the landlord and up to
+    /// `MAX_TENANTS` tenants. Can only be called once, and must be authorized
+    /// by the landlord. `rent_amount` is owed for every period of the schedule
+    /// in `terms`, in the smallest unit of `token`, which may be any Stellar
+    /// Asset Contract, native XLM included.
+    pub fn initialize(
+        env: Env,
+        landlord: Address,
+        tenants: Vec<Address>,
+        rent_amount: i128,
+        token: Address,
+        terms: Terms,
+    ) -> Result<(), Error> {
+        if storage::has_agreement(&env) {
+            return Err(Error::AlreadyInitialized);
+        }
+        landlord.require_auth();
+
+        if tenants.is_empty() || tenants.len() > MAX_TENANTS {
+            return Err(Error::InvalidTenant);
+        }
+        for (i, tenant) in tenants.iter().enumerate() {
+            if tenant == landlord || tenants.first_index_of(&tenant) != Some(i as u32) {
+                return Err(Error::InvalidTenant);
+            }
+        }
+        if rent_amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let shares = terms.split.resolve(&env, tenants.len(), rent_amount)?;
+        terms.schedule.validate()?;
+        terms.late_fee.validate()?;
+        terms.arbitration.validate(&landlord, &tenants)?;
+        if terms.security_deposit < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        if terms.contest_window == 0 {
+            return Err(Error::InvalidSchedule);
+        }
+        if let Some(feed) = terms.pricing.feed() {
+            feed.validate()?;
+        }
+        let security_shares = types::prorate(&env, &shares, rent_amount, terms.security_deposit)?;
+        let decimals = token::Client::new(&env, &token).decimals();
+
+        events::initialized(&env, &landlord, &tenants, rent_amount, &token);
+        storage::set_agreement(
+            &env,
+            &Agreement {
+                landlord,
+                tenants,
+                shares,
+                rent_amount,
+                token,
+                decimals,
+                payout: terms.payout,
+                schedule: terms.schedule,
+                late_fee: terms.late_fee,
+                arbitration: terms.arbitration,
+                security_deposit: terms.security_deposit,
+                contest_window: terms.contest_window,
+                security_shares,
+                pricing: terms.pricing,
+            },
+        );
+        transition(&env, ContractStatus::Active)?;
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Moves `amount` of the agreement token from `tenant` into the escrow and
+    /// applies it to that tenant's share of the current period. With automatic
+    /// payout, the deposit that completes a period's rent pays it out to the
+    /// landlord, and deposits roll over to the next period. A tenant paying
+    /// after the grace period is charged a late fee, which is taken from the
+    /// deposit before their share. Anything beyond the tenant's share stays in
+    /// escrow as credit for them, and is put towards their share of the next
+    /// period once collection moves on. No rent is taken until the whole
+    /// security deposit is in. When the rent is priced by an oracle, the
+    /// deposit is converted at the oracle's price first, and credit is kept in
+    /// the token.
+    pub fn deposit(env: Env, tenant: Address, amount: i128) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        tenant.require_auth();
+
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+        match status(&env, &agreement) {
+            ContractStatus::Active => {}
+            ContractStatus::Expired => return Err(Error::Expired),
+            _ => return Err(Error::InvalidState),
+        }
+        if security_held(&env, &agreement)? < agreement.security_deposit {
+            return Err(Error::ConditionNotMet);
+        }
+        let current = storage::get_current(&env);
+        if storage::get_period(&env, &agreement, current).status != PeriodStatus::Open {
+            return Err(Error::InvalidState);
+        }
+
+        let price = price(&env, &agreement)?;
+        let token = token::Client::new(&env, &agreement.token);
+        if token.balance(&tenant) < amount {
+            return Err(Error::InsufficientFunds);
+        }
+        token.transfer(&tenant, &env.current_contract_address(), &amount);
+
+        let (applied, excess) = pay(&env, &agreement, &tenant, current, amount, price)?;
+        if excess > 0 {
+            let credit = storage::get_credit(&env, &tenant);
+            storage::set_credit(&env, &tenant, checked_add(credit, excess)?);
+        }
+        events::deposited(&env, &tenant, current, applied, excess);
+
+        if storage::get_period(&env, &agreement, current).collected == agreement.rent_amount {
+            fund(&env, &agreement, current);
+            advance(&env, &agreement, current)?;
+        }
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Moves `tenant`'s part of the security deposit into the escrow. It is
+    /// kept apart from the rent and stays locked until the lease ends.
+    pub fn fund_security_deposit(env: Env, tenant: Address) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        tenant.require_auth();
+
+        let amount = agreement
+            .security_share_of(&tenant)
+            .ok_or(Error::InvalidTenant)?;
+        if status(&env, &agreement) != ContractStatus::Active {
+            return Err(Error::InvalidState);
+        }
+        if amount == 0 || storage::get_security(&env, &tenant) > 0 {
+            return Err(Error::ConditionNotMet);
+        }
+
+        let token = token::Client::new(&env, &agreement.token);
+        if token.balance(&tenant) < amount {
+            return Err(Error::InsufficientFunds);
+        }
+        token.transfer(&tenant, &env.current_contract_address(), &amount);
+        storage::set_security(&env, &tenant, amount);
+        events::security_funded(&env, &tenant, amount);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Lets `tenant` pay their share in installments that fall due at the
+    /// offsets of `plan` after each period's due date, with late fees charged
+    /// on every installment that is missed. An empty plan makes the whole
+    /// share due at once again. Needs the landlord's and the tenant's
+    /// authorization, and can only be changed before the tenant pays towards
+    /// the current period.
+    pub fn set_installments(
+        env: Env,
+        tenant: Address,
+        plan: Vec<Installment>,
+    ) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.landlord.require_auth();
+        tenant.require_auth();
+
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        if status(&env, &agreement) != ContractStatus::Active {
+            return Err(Error::InvalidState);
+        }
+        types::validate_installments(&plan, agreement.schedule.interval)?;
+        let current = storage::get_current(&env);
+        if storage::get_received(&env, current, &tenant) > 0 {
+            return Err(Error::ConditionNotMet);
+        }
+
+        storage::set_installments(&env, &tenant, &plan);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Opens a dispute over the oldest period whose rent is still in escrow,
+    /// freezing its payout and refunds until the dispute is resolved. Only
+    /// the landlord or a tenant can open one, only once something has been
+    /// deposited towards that period, and only one can be open at a time.
+    pub fn open_dispute(env: Env, party: Address, evidence_hash: BytesN<32>) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        party.require_auth();
+
+        if party != agreement.landlord && agreement.share_of(&party).is_none() {
+            return Err(Error::Unauthorized);
+        }
+        if storage::get_open_dispute(&env).is_some() {
+            return Err(Error::Disputed);
+        }
+        match status(&env, &agreement) {
+            ContractStatus::Active | ContractStatus::Fulfilled | ContractStatus::Expired => {}
+            _ => return Err(Error::InvalidState),
+        }
+        let period = storage::get_next_payout(&env);
+        if period >= agreement.schedule.periods
+            || storage::get_dispute(&env, period).is_some()
+            || frozen_deposits(&env, &agreement, period)?.1 == 0
+        {
+            return Err(Error::ConditionNotMet);
+        }
+
+        let now = env.ledger().timestamp();
+        storage::set_dispute(
+            &env,
+            &Dispute {
+                period,
+                opened_by: party.clone(),
+                opened_at: now,
+                deadline: now.saturating_add(agreement.arbitration.timeout),
+                evidence: evidence_hash.clone(),
+                status: DisputeStatus::Open,
+                votes: Map::new(&env),
+                resolved_at: None,
+                to_landlord: 0,
+                to_tenants: 0,
+            },
+        );
+        storage::set_open_dispute(&env, Some(period));
+        events::dispute_opened(&env, &party, period, &evidence_hash);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Casts `arbiter`'s vote on how to resolve the open dispute. Votes are
+    /// recorded on the dispute, and an arbiter can change theirs until the
+    /// dispute is resolved. Once the panel's threshold of arbiters agrees on
+    /// the same outcome, it is carried out: a split or refund closes the
+    /// disputed period, unless it was still being collected, in which case
+    /// it starts collecting its whole rent again. Rejecting the dispute
+    /// releases the funds as if it had never been opened.
+    pub fn resolve_dispute(env: Env, arbiter: Address, outcome: Outcome) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        arbiter.require_auth();
+
+        if !agreement.arbitration.arbiters.contains(&arbiter) {
+            return Err(Error::Unauthorized);
+        }
+        let index = storage::get_open_dispute(&env).ok_or(Error::ConditionNotMet)?;
+        let mut dispute = storage::get_dispute(&env, index).ok_or(Error::ConditionNotMet)?;
+        if let Outcome::Resolved(amount) = outcome {
+            let (_, frozen) = frozen_deposits(&env, &agreement, index)?;
+            if amount < 0 || amount > frozen {
+                return Err(Error::InvalidAmount);
+            }
+        }
+
+        dispute.votes.set(arbiter.clone(), outcome.clone());
+        events::vote_cast(&env, &arbiter, index, &outcome);
+        let support = dispute
+            .votes
+            .values()
+            .iter()
+            .filter(|vote| *vote == outcome)
+            .count() as u32;
+        if support < agreement.arbitration.threshold {
+            storage::set_dispute(&env, &dispute);
+            storage::extend_instance(&env);
+            return Ok(());
+        }
+        execute_ruling(&env, &agreement, &mut dispute, &outcome)
+    }
+
+    /// Applies the fallback configured for the arbiter panel to the open
+    /// dispute once its deadline has passed without a ruling. Anyone can
+    /// call this.
+    pub fn apply_fallback(env: Env) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+
+        let index = storage::get_open_dispute(&env).ok_or(Error::ConditionNotMet)?;
+        let mut dispute = storage::get_dispute(&env, index).ok_or(Error::ConditionNotMet)?;
+        if env.ledger().timestamp() < dispute.deadline {
+            return Err(Error::ConditionNotMet);
+        }
+        let outcome = match agreement.arbitration.fallback {
+            Fallback::Refund => Outcome::Refunded,
+            Fallback::Release => Outcome::Resolved(frozen_deposits(&env, &agreement, index)?.1),
+        };
+        execute_ruling(&env, &agreement, &mut dispute, &outcome)
+    }
+
+    /// Proposes itemized deductions from the security deposit. Only the
+    /// landlord can propose, once, after the lease has ended and before the
+    /// contest window that follows it has passed.
+    pub fn propose_deductions(env: Env, deductions: Vec<Deduction>) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.landlord.require_auth();
+
+        let now = env.ledger().timestamp();
+        let end = agreement.schedule.end();
+        if now < end || security_held(&env, &agreement)? == 0 {
+            return Err(Error::ConditionNotMet);
+        }
+        if now >= end.saturating_add(agreement.contest_window) {
+            return Err(Error::Expired);
+        }
+        if storage::get_settlement(&env).is_some() {
+            return Err(Error::InvalidState);
+        }
+        let mut total = 0;
+        for deduction in deductions.iter() {
+            if deduction.amount <= 0 {
+                return Err(Error::InvalidAmount);
+            }
+            total = checked_add(total, deduction.amount)?;
+        }
+        if total > security_held(&env, &agreement)? {
+            return Err(Error::InsufficientFunds);
+        }
+
+        storage::set_settlement(
+            &env,
+            &Settlement {
+                deductions,
+                total,
+                proposed_at: now,
+                respond_by: now.saturating_add(agreement.contest_window),
+                accepted: Vec::new(&env),
+                status: SettlementStatus::Proposed,
+                rule_by: None,
+                votes: Map::new(&env),
+            },
+        );
+        events::deductions_proposed(&env, &agreement.landlord, total);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Accepts the proposed deductions on behalf of `tenant`. Once every
+    /// tenant has accepted, the deposit can be settled right away.
+    pub fn accept_deductions(env: Env, tenant: Address) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        tenant.require_auth();
+
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        let mut settlement = open_settlement(&env)?;
+        if !settlement.accepted.contains(&tenant) {
+            settlement.accepted.push_back(tenant);
+        }
+        if settlement.accepted.len() == agreement.tenants.len() {
+            settlement.status = SettlementStatus::Accepted;
+        }
+        storage::set_settlement(&env, &settlement);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Contests the proposed deductions on behalf of `tenant`, which keeps
+    /// the whole deposit in escrow and puts the deductions to the arbiter
+    /// panel.
+    pub fn contest_deductions(env: Env, tenant: Address) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        tenant.require_auth();
+
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        let mut settlement = open_settlement(&env)?;
+        settlement.status = SettlementStatus::Contested;
+        let now = env.ledger().timestamp();
+        settlement.rule_by = Some(now.saturating_add(agreement.arbitration.timeout));
+        storage::set_settlement(&env, &settlement);
+        events::deductions_contested(&env, &tenant, settlement.total);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Casts `arbiter`'s vote on how much of the contested deductions the
+    /// landlord may keep. Votes are recorded on the settlement, and an
+    /// arbiter can change theirs until the panel rules. Once the panel's
+    /// threshold of arbiters agrees on the same amount, the deposit is
+    /// settled with that much deducted.
+    pub fn rule_on_deductions(env: Env, arbiter: Address, allowed: i128) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        arbiter.require_auth();
+
+        if !agreement.arbitration.arbiters.contains(&arbiter) {
+            return Err(Error::Unauthorized);
+        }
+        let mut settlement = storage::get_settlement(&env).ok_or(Error::ConditionNotMet)?;
+        if settlement.status != SettlementStatus::Contested {
+            return Err(Error::InvalidState);
+        }
+        if allowed < 0 || allowed > settlement.total {
+            return Err(Error::InvalidAmount);
+        }
+
+        settlement.votes.set(arbiter.clone(), allowed);
+        events::deductions_vote_cast(&env, &arbiter, allowed);
+        let support = settlement
+            .votes
+            .values()
+            .iter()
+            .filter(|vote| *vote == allowed)
+            .count() as u32;
+        if support >= agreement.arbitration.threshold {
+            release_security(&env, &agreement, allowed)?;
+            settlement.status = SettlementStatus::Settled;
+        }
+        storage::set_settlement(&env, &settlement);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Pays the accepted deductions to the landlord and refunds the rest of
+    /// the security deposit to the tenants, in proportion to what each put
+    /// up. If the landlord proposed nothing within the contest window after
+    /// the lease ended, the whole deposit is refunded, and contested
+    /// deductions the arbiter panel did not rule on in time are settled
+    /// according to its fallback. Anyone can call this.
+    pub fn settle_security_deposit(env: Env) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+
+        if security_held(&env, &agreement)? == 0 {
+            return Err(Error::ConditionNotMet);
+        }
+        let now = env.ledger().timestamp();
+        let mut settlement = storage::get_settlement(&env);
+        let deducted = match &settlement {
+            Some(settlement) => match settlement_status(&env, settlement) {
+                SettlementStatus::Accepted => settlement.total,
+                SettlementStatus::Contested => match settlement.rule_by {
+                    Some(rule_by) if now >= rule_by => match agreement.arbitration.fallback {
+                        Fallback::Refund => 0,
+                        Fallback::Release => settlement.total,
+                    },
+                    _ => return Err(Error::ConditionNotMet),
+                },
+                SettlementStatus::Proposed => return Err(Error::ConditionNotMet),
+                SettlementStatus::Settled => return Err(Error::InvalidState),
+            },
+            None => {
+                let end = agreement.schedule.end();
+                if now < end.saturating_add(agreement.contest_window) {
+                    return Err(Error::ConditionNotMet);
+                }
+                0
+            }
+        };
+
+        release_security(&env, &agreement, deducted)?;
+        if let Some(settlement) = settlement.as_mut() {
+            settlement.status = SettlementStatus::Settled;
+            storage::set_settlement(&env, settlement);
+        }
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Pays out the rent of every fully funded period to the landlord, except
+    /// one frozen by a dispute. Only available to the landlord, and only once
+    /// at least one period's rent has been fully collected.
+    pub fn withdraw(env: Env) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.landlord.require_auth();
+
+        let mut next = storage::get_next_payout(&env);
+        let mut paid_out = false;
+        while next < agreement.schedule.periods {
+            let mut period = storage::get_period(&env, &agreement, next);
+            match period.status {
+                _ if is_frozen(&env, next) => {}
+                PeriodStatus::Funded => {
+                    disburse(&env, &agreement, next, &mut period);
+                    paid_out = true;
+                }
+                PeriodStatus::Disbursed | PeriodStatus::Arbitrated => {}
+                _ => break,
+            }
+            next += 1;
+        }
+        if !paid_out {
+            return Err(Error::ConditionNotMet);
+        }
+        storage::set_next_payout(&env, next);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Returns to `tenant` what they deposited for the period that expired.
+    /// Only available once the escrow has expired, and only once per tenant.
+    pub fn claim_refund(env: Env, tenant: Address) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        tenant.require_auth();
+
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        if status(&env, &agreement) != ContractStatus::Expired {
+            return Err(Error::InvalidState);
+        }
+        let current = storage::get_current(&env);
+        if is_frozen(&env, current) {
+            return Err(Error::Disputed);
+        }
+        if storage::get_status(&env) == ContractStatus::Active {
+            transition(&env, ContractStatus::Expired)?;
+            let mut period = storage::get_period(&env, &agreement, current);
+            period.status = PeriodStatus::Expired;
+            storage::set_period(&env, current, &period);
+        }
+
+        let deposited = deposited(&env, current, &tenant);
+        if deposited == 0 || storage::is_refunded(&env, current, &tenant) {
+            return Err(Error::ConditionNotMet);
+        }
+        token::Client::new(&env, &agreement.token).transfer(
+            &env.current_contract_address(),
+            &tenant,
+            &deposited,
+        );
+        storage::set_refunded(&env, current, &tenant);
+        storage::set_held(&env, storage::get_held(&env) - deposited);
+        events::refunded(&env, &tenant, current, deposited);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Returns `tenant`'s unused credit to them once the lease is over,
+    /// that is once the escrow is fulfilled, has expired or was cancelled.
+    /// Until then, the credit goes towards the tenant's next period.
+    pub fn withdraw_credit(env: Env, tenant: Address) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        tenant.require_auth();
+
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        match status(&env, &agreement) {
+            ContractStatus::Fulfilled | ContractStatus::Expired | ContractStatus::Cancelled => {}
+            _ => return Err(Error::InvalidState),
+        }
+        let credit = storage::get_credit(&env, &tenant);
+        if credit == 0 {
+            return Err(Error::ConditionNotMet);
+        }
+
+        token::Client::new(&env, &agreement.token).transfer(
+            &env.current_contract_address(),
+            &tenant,
+            &credit,
+        );
+        storage::set_credit(&env, &tenant, 0);
+        events::credit_withdrawn(&env, &tenant, credit);
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Cancels the agreement, returning to every tenant whatever they have
+    /// in escrow that has not been paid out to the landlord, security
+    /// deposit included. Needs the landlord's authorization, and every
+    /// tenant's as well once anyone has paid.
+    pub fn cancel(env: Env) -> Result<(), Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.landlord.require_auth();
+        if status(&env, &agreement) != ContractStatus::Active {
+            return Err(Error::InvalidState);
+        }
+        if storage::get_open_dispute(&env).is_some() {
+            return Err(Error::Disputed);
+        }
+        if has_payments(&env, &agreement) {
+            for tenant in agreement.tenants.iter() {
+                tenant.require_auth();
+            }
+        }
+
+        // A rejected dispute can leave periods that were paid out after
+        // `NextPayout`, so only those still holding rent are cancelled.
+        let mut unpaid = Vec::new(&env);
+        for index in storage::get_next_payout(&env)..=storage::get_current(&env) {
+            let mut period = storage::get_period(&env, &agreement, index);
+            if matches!(period.status, PeriodStatus::Open | PeriodStatus::Funded) {
+                period.status = PeriodStatus::Cancelled;
+                storage::set_period(&env, index, &period);
+                unpaid.push_back(index);
+            }
+        }
+
+        let token = token::Client::new(&env, &agreement.token);
+        for tenant in agreement.tenants.iter() {
+            for index in unpaid.iter() {
+                let deposited = deposited(&env, index, &tenant);
+                if deposited > 0 && !storage::is_refunded(&env, index, &tenant) {
+                    token.transfer(&env.current_contract_address(), &tenant, &deposited);
+                    storage::set_refunded(&env, index, &tenant);
+                    events::refunded(&env, &tenant, index, deposited);
+                }
+            }
+            let credit = storage::get_credit(&env, &tenant);
+            if credit > 0 {
+                token.transfer(&env.current_contract_address(), &tenant, &credit);
+                storage::set_credit(&env, &tenant, 0);
+                events::credit_withdrawn(&env, &tenant, credit);
+            }
+            let security = storage::get_security(&env, &tenant);
+            if security > 0 {
+                token.transfer(&env.current_contract_address(), &tenant, &security);
+                storage::set_security(&env, &tenant, 0);
+                events::security_refunded(&env, &tenant, security);
+            }
+        }
+        storage::set_held(&env, 0);
+        transition(&env, ContractStatus::Cancelled)?;
+        storage::extend_instance(&env);
+        Ok(())
+    }
+
+    /// Returns the agreement the escrow was initialized with.
+    pub fn get_agreement(env: Env) -> Result<Agreement, Error> {
+        storage::get_agreement(&env)
+    }
+
+    /// Returns the current status of the escrow. An escrow whose current
+    /// period has passed its deadline is reported as expired even before
+    /// anyone has acted on it.
+    pub fn get_status(env: Env) -> ContractStatus {
+        match storage::get_agreement(&env) {
+            Ok(agreement) => status(&env, &agreement),
+            Err(_) => storage::get_status(&env),
+        }
+    }
+
+    /// Returns the index of the period deposits currently go to.
+    pub fn get_current_period(env: Env) -> Result<u32, Error> {
+        storage::get_agreement(&env)?;
+        Ok(storage::get_current(&env))
+    }
+
+    /// Returns the due date, collected rent and payout status of `period`.
+    pub fn get_period(env: Env, period: u32) -> Result<Period, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        if period >= agreement.schedule.periods {
+            return Err(Error::InvalidPeriod);
+        }
+        Ok(storage::peek_period(&env, &agreement, period))
+    }
+
+    /// Returns the payment history of up to `limit` periods starting at
+    /// `start`. At most `Agreement::history_page` periods are returned per
+    /// call, fewer the more tenants there are, so a long lease is read page
+    /// by page.
+    pub fn get_history(env: Env, start: u32, limit: u32) -> Result<Vec<PeriodRecord>, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        if start >= agreement.schedule.periods {
+            return Err(Error::InvalidPeriod);
+        }
+
+        let end = start
+            .saturating_add(limit.min(agreement.history_page()))
+            .min(agreement.schedule.periods);
+        let mut history = Vec::new(&env);
+        for index in start..end {
+            let period = storage::peek_period(&env, &agreement, index);
+            let mut payments = Vec::new(&env);
+            for tenant in agreement.tenants.iter() {
+                payments.push_back(Payment {
+                    paid: storage::peek_paid(&env, index, &tenant),
+                    late_fee: storage::peek_late_fee(&env, index, &tenant),
+                    paid_at: storage::peek_paid_at(&env, index, &tenant),
+                    tenant,
+                });
+            }
+            history.push_back(PeriodRecord {
+                period: index,
+                due_date: period.due_date,
+                payments,
+                late_fees: period.late_fees,
+                status: period.status,
+                paid_out_at: period.paid_out_at,
+            });
+        }
+        Ok(history)
+    }
+
+    /// Returns the rent currently held in escrow, as recorded by the
+    /// contract's own accounting. The security deposit is reported
+    /// separately by `get_security_deposit`.
+    pub fn get_balance(env: Env) -> Result<i128, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        let mut balance = storage::get_held(&env);
+        for tenant in agreement.tenants.iter() {
+            balance = checked_add(balance, storage::get_credit(&env, &tenant))?;
+        }
+        Ok(balance)
+    }
+
+    /// Returns the dispute over `period`, if one was ever opened.
+    pub fn get_dispute(env: Env, period: u32) -> Result<Option<Dispute>, Error> {
+        storage::get_agreement(&env)?;
+        Ok(storage::peek_dispute(&env, period))
+    }
+
+    /// Returns the dispute that is currently open, if any.
+    pub fn get_open_dispute(env: Env) -> Result<Option<Dispute>, Error> {
+        storage::get_agreement(&env)?;
+        Ok(storage::get_open_dispute(&env).and_then(|period| storage::peek_dispute(&env, period)))
+    }
+
+    /// Returns the security deposit owed, how much of it is held and until
+    /// when it is locked.
+    pub fn get_security_deposit(env: Env) -> Result<SecurityDeposit, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        Ok(SecurityDeposit {
+            amount: agreement.security_deposit,
+            held: security_held(&env, &agreement)?,
+            locked_until: agreement.schedule.end(),
+        })
+    }
+
+    /// Returns the landlord's proposed deductions from the security deposit,
+    /// if any. Deductions nobody contested in time are reported as accepted.
+    pub fn get_settlement(env: Env) -> Result<Option<Settlement>, Error> {
+        storage::get_agreement(&env)?;
+        Ok(storage::get_settlement(&env).map(|mut settlement| {
+            settlement.status = settlement_status(&env, &settlement);
+            settlement
+        }))
+    }
+
+    /// Returns how much of the security deposit `tenant` has put up.
+    pub fn get_security_paid(env: Env, tenant: Address) -> Result<i128, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        Ok(storage::get_security(&env, &tenant))
+    }
+
+    /// Returns how much `tenant` has paid towards the current period.
+    pub fn get_paid(env: Env, tenant: Address) -> Result<i128, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        Ok(storage::peek_paid(
+            &env,
+            storage::get_current(&env),
+            &tenant,
+        ))
+    }
+
+    /// Returns the amount `tenant` paid beyond their share that has not gone
+    /// towards a later period yet, which stays in escrow on their behalf.
+    pub fn get_credit(env: Env, tenant: Address) -> Result<i128, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        Ok(storage::get_credit(&env, &tenant))
+    }
+
+    /// Returns the installment plan agreed with `tenant`, empty if their
+    /// whole share falls due at once.
+    pub fn get_installments(env: Env, tenant: Address) -> Result<Vec<Installment>, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        Ok(storage::get_installments(&env, &tenant))
+    }
+
+    /// Returns the late fee `tenant` has accrued for the current period so
+    /// far, whether or not it has been paid yet.
+    pub fn get_late_fee(env: Env, tenant: Address) -> Result<i128, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        late_fee(&env, &agreement, storage::get_current(&env), &tenant)
+    }
+
+    /// Returns how much `tenant` still owes towards their share of the
+    /// current period, including any unpaid late fee.
+    pub fn get_outstanding(env: Env, tenant: Address) -> Result<i128, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        let share = agreement.share_of(&tenant).ok_or(Error::InvalidTenant)?;
+        let current = storage::get_current(&env);
+        let paid = storage::peek_paid(&env, current, &tenant);
+        let fee = late_fee(&env, &agreement, current, &tenant)?
+            - storage::peek_late_fee(&env, current, &tenant);
+        Ok((share - paid).max(0) + fee)
+    }
+
+    /// Returns how much is still missing before the current period's rent
+    /// is fully collected.
+    pub fn get_remaining(env: Env) -> Result<i128, Error> {
+        let agreement = storage::get_agreement(&env)?;
+        let period = storage::peek_period(&env, &agreement, storage::get_current(&env));
+        Ok((agreement.rent_amount - period.collected).max(0))
+    }
+}
+
+/// Returns the status of the escrow at the current ledger time. The stored
+/// status only moves to expired once someone acts on it, so an active escrow
+/// whose current period is past its deadline is treated as expired.
+fn status(env: &Env, agreement: &Agreement) -> ContractStatus {
+    let status = storage::get_status(env);
+    let deadline = agreement.schedule.deadline(storage::get_current(env));
+    if status == ContractStatus::Active && env.ledger().timestamp() >= deadline {
+        ContractStatus::Expired
+    } else {
+        status
+    }
+}
+
+/// Returns whether any tenant has paid anything into the escrow yet.
+fn has_payments(env: &Env, agreement: &Agreement) -> bool {
+    storage::get_current(env) > 0
+        || storage::get_held(env) > 0
+        || agreement.tenants.iter().any(|tenant| {
+            storage::get_credit(env, &tenant) > 0 || storage::get_security(env, &tenant) > 0
+        })
+}
+
+/// Moves collection on from the funded period `index`, fulfilling the
+/// escrow after the last period unless that period is disputed, in which
+/// case the ruling decides how the lease ends. Tenants' credit is applied to
+/// the next period, and if that collects its rent, collection moves on
+/// again.
+fn advance(env: &Env, agreement: &Agreement, index: u32) -> Result<(), Error> {
+    let mut index = index;
+    loop {
+        if index + 1 == agreement.schedule.periods {
+            if is_frozen(env, index) {
+                return Ok(());
+            }
+            return transition(env, ContractStatus::Fulfilled);
+        }
+        index += 1;
+        storage::set_current(env, index);
+        if !apply_credits(env, agreement, index)? {
+            return Ok(());
+        }
+        fund(env, agreement, index);
+    }
+}
+
+/// Marks the fully collected period `index` as funded, paying it out right
+/// away with automatic payout unless it is disputed.
+fn fund(env: &Env, agreement: &Agreement, index: u32) {
+    let mut period = storage::get_period(env, agreement, index);
+    period.status = PeriodStatus::Funded;
+    storage::set_period(env, index, &period);
+    if agreement.payout == PayoutMode::Automatic && !is_frozen(env, index) {
+        disburse(env, agreement, index, &mut period);
+        storage::set_next_payout(env, index + 1);
+    }
+}
+
+/// Applies each tenant's credit to their share of `index`, returning whether
+/// that collects the period's whole rent. With oracle pricing, credit stays
+/// where it is if there is no fresh price to convert it at.
+fn apply_credits(env: &Env, agreement: &Agreement, index: u32) -> Result<bool, Error> {
+    let mut credits = Vec::new(env);
+    for tenant in agreement.tenants.iter() {
+        let credit = storage::get_credit(env, &tenant);
+        if credit > 0 {
+            credits.push_back((tenant, credit));
+        }
+    }
+    if credits.is_empty() {
+        return Ok(false);
+    }
+    let price = match price(env, agreement) {
+        Ok(price) => price,
+        Err(_) => return Ok(false),
+    };
+
+    for (tenant, credit) in credits.iter() {
+        let (applied, excess) = pay(env, agreement, &tenant, index, credit, price)?;
+        storage::set_credit(env, &tenant, excess);
+        events::credit_applied(env, &tenant, index, applied, excess);
+    }
+    let period = storage::get_period(env, agreement, index);
+    Ok(period.collected == agreement.rent_amount)
+}
+
+/// Puts `amount` of the token, already in the escrow, towards `tenant`'s
+/// late fee and then their share of `index`. Returns how much of the share
+/// it paid and the tokens left over.
+fn pay(
+    env: &Env,
+    agreement: &Agreement,
+    tenant: &Address,
+    index: u32,
+    amount: i128,
+    price: Option<i128>,
+) -> Result<(i128, i128), Error> {
+    let share = agreement.share_of(tenant).ok_or(Error::InvalidTenant)?;
+    let mut period = storage::get_period(env, agreement, index);
+    let paid = storage::get_paid(env, index, tenant);
+    let fee_paid = storage::get_late_fee(env, index, tenant);
+    let value = to_rent(agreement, price, amount)?;
+    let fee = value.min(late_fee(env, agreement, index, tenant)? - fee_paid);
+    if fee > 0 {
+        storage::set_late_fee(env, index, tenant, checked_add(fee_paid, fee)?);
+        period.late_fees = checked_add(period.late_fees, fee)?;
+        events::late_fee(env, tenant, index, fee);
+    }
+    let mut applied = (value - fee).min(share - paid);
+    if let Some(feed) = agreement.pricing.feed() {
+        if feed.absorbs(share - paid - applied, share) {
+            applied = share - paid;
+        }
+    }
+    storage::set_paid(env, index, tenant, checked_add(paid, applied)?);
+    cover_installments(env, index, tenant, share, paid + applied);
+    period.collected = checked_add(period.collected, applied)?;
+    let excess = if value > fee + applied {
+        to_tokens(agreement, price, value - fee - applied)?
+    } else {
+        0
+    };
+    let kept = amount - excess;
+    let received = storage::get_received(env, index, tenant);
+    storage::set_received(env, index, tenant, checked_add(received, kept)?);
+    period.received = checked_add(period.received, kept)?;
+    storage::set_held(env, checked_add(storage::get_held(env), kept)?);
+    if fee + applied > 0 {
+        storage::set_paid_at(env, index, tenant, env.ledger().timestamp());
+    }
+    storage::set_period(env, index, &period);
+    Ok((applied, excess))
+}
+
+/// Carries out `outcome` on the open `dispute`.
+fn execute_ruling(
+    env: &Env,
+    agreement: &Agreement,
+    dispute: &mut Dispute,
+    outcome: &Outcome,
+) -> Result<(), Error> {
+    let index = dispute.period;
+    storage::set_open_dispute(env, None);
+    let mut period = storage::get_period(env, agreement, index);
+    if period.status == PeriodStatus::Funded
+        && index == storage::get_current(env)
+        && storage::get_status(env) == ContractStatus::Active
+    {
+        // `advance` held back the end of the lease for the ruling on its
+        // last period, which ends it according to where the rent goes.
+        let released = match outcome {
+            Outcome::Resolved(amount) => *amount > 0,
+            Outcome::Rejected => true,
+            Outcome::Refunded => false,
+        };
+        transition(
+            env,
+            if released {
+                ContractStatus::Fulfilled
+            } else {
+                ContractStatus::Cancelled
+            },
+        )?;
+    }
+
+    let to_landlord = match outcome {
+        Outcome::Resolved(amount) => *amount,
+        Outcome::Refunded => 0,
+        Outcome::Rejected => {
+            if period.status == PeriodStatus::Funded && agreement.payout == PayoutMode::Automatic {
+                disburse(env, agreement, index, &mut period);
+                let next = storage::get_next_payout(env).max(index + 1);
+                storage::set_next_payout(env, next);
+            } else {
+                // `withdraw` skips the period while it is frozen, so bring it
+                // back within reach of the next withdrawal.
+                let next = storage::get_next_payout(env).min(index);
+                storage::set_next_payout(env, next);
+            }
+            return close_dispute(env, dispute, outcome, 0, 0);
+        }
+    };
+
+    let (deposits, frozen) = frozen_deposits(env, agreement, index)?;
+
+    let token = token::Client::new(env, &agreement.token);
+    if to_landlord > 0 {
+        token.transfer(
+            &env.current_contract_address(),
+            &agreement.landlord,
+            &to_landlord,
+        );
+        period.paid_out_at = Some(env.ledger().timestamp());
+    }
+    if frozen > 0 {
+        let refunds = types::prorate(env, &deposits, frozen, frozen - to_landlord)?;
+        for (i, tenant) in agreement.tenants.iter().enumerate() {
+            let refund = refunds.get_unchecked(i as u32);
+            if refund > 0 {
+                token.transfer(&env.current_contract_address(), &tenant, &refund);
+                events::refunded(env, &tenant, index, refund);
+            }
+            if deposits.get_unchecked(i as u32) > 0 {
+                storage::set_refunded(env, index, &tenant);
+            }
+        }
+    }
+    storage::set_held(env, storage::get_held(env) - frozen);
+    if period.status == PeriodStatus::Open {
+        storage::clear_period(env, index, &agreement.tenants);
+    } else {
+        period.status = PeriodStatus::Arbitrated;
+        storage::set_period(env, index, &period);
+        if storage::get_next_payout(env) == index {
+            storage::set_next_payout(env, index + 1);
+        }
+    }
+    close_dispute(env, dispute, outcome, to_landlord, frozen - to_landlord)
+}
+
+/// Returns what each tenant deposited towards `index` that has not been
+/// refunded, along with the total, which is what a dispute over it freezes.
+fn frozen_deposits(
+    env: &Env,
+    agreement: &Agreement,
+    index: u32,
+) -> Result<(Vec<i128>, i128), Error> {
+    let mut deposits = Vec::new(env);
+    let mut frozen = 0;
+    for tenant in agreement.tenants.iter() {
+        let deposited = if storage::is_refunded(env, index, &tenant) {
+            0
+        } else {
+            deposited(env, index, &tenant)
+        };
+        deposits.push_back(deposited);
+        frozen = checked_add(frozen, deposited)?;
+    }
+    Ok((deposits, frozen))
+}
+
+/// Records the outcome of `dispute` and announces it.
+fn close_dispute(
+    env: &Env,
+    dispute: &mut Dispute,
+    outcome: &Outcome,
+    to_landlord: i128,
+    to_tenants: i128,
+) -> Result<(), Error> {
+    dispute.status = outcome.status();
+    dispute.resolved_at = Some(env.ledger().timestamp());
+    dispute.to_landlord = to_landlord;
+    dispute.to_tenants = to_tenants;
+    storage::set_dispute(env, dispute);
+    events::dispute_resolved(
+        env,
+        &dispute.opened_by,
+        dispute.period,
+        dispute.status,
+        to_landlord,
+        to_tenants,
+    );
+    storage::extend_instance(env);
+    Ok(())
+}
+
+/// Returns whether the funds of `index` are frozen by an open dispute.
+fn is_frozen(env: &Env, index: u32) -> bool {
+    storage::get_open_dispute(env) == Some(index)
+}
+
+/// Returns the status of `settlement` at the current ledger time, treating
+/// deductions nobody contested before the deadline as accepted.
+fn settlement_status(env: &Env, settlement: &Settlement) -> SettlementStatus {
+    if settlement.status == SettlementStatus::Proposed
+        && env.ledger().timestamp() >= settlement.respond_by
+    {
+        SettlementStatus::Accepted
+    } else {
+        settlement.status
+    }
+}
+
+/// Returns the proposed settlement if tenants can still respond to it.
+fn open_settlement(env: &Env) -> Result<Settlement, Error> {
+    let settlement = storage::get_settlement(env).ok_or(Error::ConditionNotMet)?;
+    if settlement_status(env, &settlement) != SettlementStatus::Proposed {
+        return Err(Error::InvalidState);
+    }
+    Ok(settlement)
+}
+
+/// Pays `deducted` of the security deposit to the landlord and refunds the
+/// rest to the tenants, in proportion to what each put up.
+fn release_security(env: &Env, agreement: &Agreement, deducted: i128) -> Result<(), Error> {
+    let held = security_held(env, agreement)?;
+    let token = token::Client::new(env, &agreement.token);
+    if deducted > 0 {
+        token.transfer(
+            &env.current_contract_address(),
+            &agreement.landlord,
+            &deducted,
+        );
+    }
+    let mut paid = Vec::new(env);
+    for tenant in agreement.tenants.iter() {
+        paid.push_back(storage::get_security(env, &tenant));
+    }
+    let deductions = types::prorate(env, &paid, held, deducted)?;
+    for (index, tenant) in agreement.tenants.iter().enumerate() {
+        let refund = paid.get_unchecked(index as u32) - deductions.get_unchecked(index as u32);
+        if refund > 0 {
+            token.transfer(&env.current_contract_address(), &tenant, &refund);
+            events::security_refunded(env, &tenant, refund);
+        }
+        storage::set_security(env, &tenant, 0);
+    }
+    events::security_settled(env, &agreement.landlord, deducted, held - deducted);
+    Ok(())
+}
+
+/// Returns the security deposit currently held for all tenants.
+fn security_held(env: &Env, agreement: &Agreement) -> Result<i128, Error> {
+    let mut held = 0;
+    for tenant in agreement.tenants.iter() {
+        held = checked_add(held, storage::get_security(env, &tenant))?;
+    }
+    Ok(held)
+}
+
+/// Returns the late fee `tenant` owes for `index`. Each installment of the
+/// tenant's share is charged on its own, and its fee keeps accruing until it
+/// is paid off. Fees are settled before the share itself, so once the share
+/// is paid the fee is whatever the tenant paid in fees. Views call it too, so
+/// it reads without extending any TTL.
+fn late_fee(env: &Env, agreement: &Agreement, index: u32, tenant: &Address) -> Result<i128, Error> {
+    let share = agreement.share_of(tenant).ok_or(Error::InvalidTenant)?;
+    let fee_paid = storage::peek_late_fee(env, index, tenant);
+    if storage::peek_paid(env, index, tenant) >= share {
+        return Ok(fee_paid);
+    }
+    let due_date = agreement.schedule.due_date(index);
+    let now = env.ledger().timestamp();
+    let plan = storage::get_installments(env, tenant);
+    if plan.is_empty() {
+        let charged = agreement.late_fee.charge(share, due_date, now)?;
+        return Ok(charged.max(fee_paid));
+    }
+
+    let covered = storage::peek_covered(env, index, tenant);
+    let totals = types::installment_totals(env, &plan, share);
+    let mut charged = 0;
+    let mut due_before = 0;
+    for (i, installment) in plan.iter().enumerate() {
+        let total = totals.get_unchecked(i as u32);
+        let until = covered.get(i as u32).unwrap_or(now);
+        let fee = agreement.late_fee.charge(
+            total - due_before,
+            due_date.saturating_add(installment.offset),
+            until,
+        )?;
+        charged = checked_add(charged, fee)?;
+        due_before = total;
+    }
+    Ok(charged.max(fee_paid))
+}
+
+/// Records when each installment of `tenant`'s share of `index` that `paid`
+/// now pays off was paid off, so its late fee stops accruing.
+fn cover_installments(env: &Env, index: u32, tenant: &Address, share: i128, paid: i128) {
+    let plan = storage::get_installments(env, tenant);
+    if plan.is_empty() {
+        return;
     }
+    let totals = types::installment_totals(env, &plan, share);
+    let mut covered = storage::get_covered(env, index, tenant);
+    let before = covered.len();
+    while covered.len() < totals.len() && paid >= totals.get_unchecked(covered.len()) {
+        covered.push_back(env.ledger().timestamp());
+    }
+    if covered.len() > before {
+        storage::set_covered(env, index, tenant, &covered);
+    }
+}
+
+/// Returns the tokens `tenant` deposited towards `index`, late fees
+/// included.
+fn deposited(env: &Env, index: u32, tenant: &Address) -> i128 {
+    storage::get_received(env, index, tenant)
+}
+
+/// Returns the oracle's price of the token if the rent is priced by one,
+/// rejecting a missing price or one older than the agreement allows.
+fn price(env: &Env, agreement: &Agreement) -> Result<Option<i128>, Error> {
+    let feed = match agreement.pricing.feed() {
+        Some(feed) => feed,
+        None => return Ok(None),
+    };
+    let data = OracleClient::new(env, &feed.oracle)
+        .lastprice(&agreement.token)
+        .ok_or(Error::StalePrice)?;
+    let age = env.ledger().timestamp().saturating_sub(data.timestamp);
+    if data.price <= 0 || age > feed.max_age {
+        return Err(Error::StalePrice);
+    }
+    Ok(Some(data.price))
+}
+
+/// Converts `amount` of the token into the currency the rent is set in.
+fn to_rent(agreement: &Agreement, price: Option<i128>, amount: i128) -> Result<i128, Error> {
+    match price {
+        Some(price) => {
+            Ok(amount.checked_mul(price).ok_or(Error::Overflow)? / token_unit(agreement)?)
+        }
+        None => Ok(amount),
+    }
+}
+
+/// Converts `value` in the currency the rent is set in into the token.
+fn to_tokens(agreement: &Agreement, price: Option<i128>, value: i128) -> Result<i128, Error> {
+    match price {
+        Some(price) => Ok(value
+            .checked_mul(token_unit(agreement)?)
+            .ok_or(Error::Overflow)?
+            / price),
+        None => Ok(value),
+    }
+}
+
+/// Returns the number of smallest units in one whole token.
+fn token_unit(agreement: &Agreement) -> Result<i128, Error> {
+    10_i128
+        .checked_pow(agreement.decimals)
+        .ok_or(Error::Overflow)
+}
+
+/// Moves the escrow to `next`, rejecting transitions the lifecycle does not
+/// allow.
+fn transition(env: &Env, next: ContractStatus) -> Result<(), Error> {
+    let current = storage::get_status(env);
+    if !current.can_transition_to(next) {
+        return Err(Error::InvalidState);
+    }
+    storage::set_status(env, next);
+    events::status_changed(env, current, next);
+    Ok(())
+}
+
+fn checked_add(a: i128, b: i128) -> Result<i128, Error> {
+    a.checked_add(b).ok_or(Error::Overflow)
+}
+
+/// Pays the rent and late fees collected for `index` out to the landlord.
+fn disburse(env: &Env, agreement: &Agreement, index: u32, period: &mut Period) {
+    let amount = period.received;
+    token::Client::new(env, &agreement.token).transfer(
+        &env.current_contract_address(),
+        &agreement.landlord,
+        &amount,
+    );
+    storage::set_held(env, storage::get_held(env) - amount);
+    period.status = PeriodStatus::Disbursed;
+    period.paid_out_at = Some(env.ledger().timestamp());
+    storage::set_period(env, index, period);
+    events::disbursed(env, &agreement.landlord, index, amount);
 }
 
 mod test;
@@ -0,0 +1,21 @@
+//! Client for the price oracle that prices rent in a quote currency.
+//!
+//! Any contract exposing `lastprice` can serve as the oracle. Prices are in
+//! the smallest unit of the quote currency per whole token, the same unit the
+//! rent of an oracle-priced agreement is set in.
+
+use soroban_sdk::{contractclient, contracttype, Address, Env};
+
+/// A price reported by the oracle and the time it was recorded at.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+#[contractclient(name = "OracleClient")]
+pub trait Oracle {
+    /// Returns the latest price of `asset`, if the oracle has one.
+    fn lastprice(env: Env, asset: Address) -> Option<PriceData>;
+}
@@ -0,0 +1,357 @@
+use soroban_sdk::{contracttype, Address, Env, TryFromVal, Val, Vec};
+
+use crate::error::Error;
+use crate::types::{
+    Agreement, ContractStatus, Dispute, Installment, Period, PeriodStatus, Settlement,
+};
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const INSTANCE_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+const PERSISTENT_BUMP_AMOUNT: u32 = 120 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - 30 * DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Agreement,
+    Status,
+    /// Index of the period deposits currently go to.
+    Current,
+    /// Index of the first period that has not been paid out.
+    NextPayout,
+    /// Rent collected but not yet paid out, across all periods.
+    Held,
+    Credit(Address),
+    /// Security deposit a tenant has put up, kept apart from the rent.
+    Security(Address),
+    /// Installment plan agreed with a tenant.
+    Installments(Address),
+    Settlement,
+    /// Period of the dispute that is currently open, if any.
+    OpenDispute,
+    Period(u32),
+    Paid(u32, Address),
+    /// Late fees a tenant has paid for a period.
+    LateFee(u32, Address),
+    /// When a tenant last paid towards a period.
+    PaidAt(u32, Address),
+    /// Tokens a tenant has put towards a period, late fees included.
+    Received(u32, Address),
+    /// When each installment of a tenant's share of a period was paid off.
+    Covered(u32, Address),
+    Refunded(u32, Address),
+    Dispute(u32),
+}
+
+pub fn extend_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn extend_persistent(env: &Env, key: &DataKey) {
+    env.storage().persistent().extend_ttl(
+        key,
+        PERSISTENT_LIFETIME_THRESHOLD,
+        PERSISTENT_BUMP_AMOUNT,
+    );
+}
+
+/// Reads `key` from persistent storage without extending its TTL, so views
+/// do not write to the ledger.
+fn peek<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    env.storage().persistent().get(key)
+}
+
+pub fn has_agreement(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Agreement)
+}
+
+pub fn get_agreement(env: &Env) -> Result<Agreement, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Agreement)
+        .ok_or(Error::NotInitialized)
+}
+
+pub fn set_agreement(env: &Env, agreement: &Agreement) {
+    env.storage().instance().set(&DataKey::Agreement, agreement);
+}
+
+pub fn get_status(env: &Env) -> ContractStatus {
+    env.storage()
+        .instance()
+        .get(&DataKey::Status)
+        .unwrap_or(ContractStatus::Inactive)
+}
+
+pub fn set_status(env: &Env, status: ContractStatus) {
+    env.storage().instance().set(&DataKey::Status, &status);
+}
+
+pub fn get_current(env: &Env) -> u32 {
+    env.storage().instance().get(&DataKey::Current).unwrap_or(0)
+}
+
+pub fn set_current(env: &Env, period: u32) {
+    env.storage().instance().set(&DataKey::Current, &period);
+}
+
+pub fn get_next_payout(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::NextPayout)
+        .unwrap_or(0)
+}
+
+pub fn set_next_payout(env: &Env, period: u32) {
+    env.storage().instance().set(&DataKey::NextPayout, &period);
+}
+
+pub fn get_held(env: &Env) -> i128 {
+    env.storage().instance().get(&DataKey::Held).unwrap_or(0)
+}
+
+pub fn set_held(env: &Env, amount: i128) {
+    env.storage().instance().set(&DataKey::Held, &amount);
+}
+
+pub fn get_credit(env: &Env, tenant: &Address) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::Credit(tenant.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_credit(env: &Env, tenant: &Address, amount: i128) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Credit(tenant.clone()), &amount);
+}
+
+pub fn get_security(env: &Env, tenant: &Address) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::Security(tenant.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_security(env: &Env, tenant: &Address, amount: i128) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Security(tenant.clone()), &amount);
+}
+
+pub fn get_installments(env: &Env, tenant: &Address) -> Vec<Installment> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Installments(tenant.clone()))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+pub fn set_installments(env: &Env, tenant: &Address, plan: &Vec<Installment>) {
+    let key = DataKey::Installments(tenant.clone());
+    if plan.is_empty() {
+        env.storage().instance().remove(&key);
+    } else {
+        env.storage().instance().set(&key, plan);
+    }
+}
+
+pub fn get_settlement(env: &Env) -> Option<Settlement> {
+    env.storage().instance().get(&DataKey::Settlement)
+}
+
+pub fn set_settlement(env: &Env, settlement: &Settlement) {
+    env.storage()
+        .instance()
+        .set(&DataKey::Settlement, settlement);
+}
+
+pub fn get_open_dispute(env: &Env) -> Option<u32> {
+    env.storage().instance().get(&DataKey::OpenDispute)
+}
+
+pub fn set_open_dispute(env: &Env, period: Option<u32>) {
+    match period {
+        Some(period) => env.storage().instance().set(&DataKey::OpenDispute, &period),
+        None => env.storage().instance().remove(&DataKey::OpenDispute),
+    }
+}
+
+/// Returns the record for `period`, or a fresh open one if nothing has been
+/// collected for it yet.
+pub fn get_period(env: &Env, agreement: &Agreement, period: u32) -> Period {
+    let key = DataKey::Period(period);
+    match env.storage().persistent().get(&key) {
+        Some(record) => {
+            extend_persistent(env, &key);
+            record
+        }
+        None => open_period(agreement, period),
+    }
+}
+
+/// Like [`get_period`], without extending the record's TTL.
+pub fn peek_period(env: &Env, agreement: &Agreement, period: u32) -> Period {
+    peek(env, &DataKey::Period(period)).unwrap_or_else(|| open_period(agreement, period))
+}
+
+fn open_period(agreement: &Agreement, period: u32) -> Period {
+    Period {
+        due_date: agreement.schedule.due_date(period),
+        collected: 0,
+        late_fees: 0,
+        received: 0,
+        status: PeriodStatus::Open,
+        paid_out_at: None,
+    }
+}
+
+pub fn set_period(env: &Env, period: u32, record: &Period) {
+    let key = DataKey::Period(period);
+    env.storage().persistent().set(&key, record);
+    extend_persistent(env, &key);
+}
+
+/// Forgets everything the `tenants` paid towards `period`, so it collects
+/// its rent from scratch.
+pub fn clear_period(env: &Env, period: u32, tenants: &Vec<Address>) {
+    let persistent = env.storage().persistent();
+    persistent.remove(&DataKey::Period(period));
+    for tenant in tenants.iter() {
+        persistent.remove(&DataKey::Paid(period, tenant.clone()));
+        persistent.remove(&DataKey::LateFee(period, tenant.clone()));
+        persistent.remove(&DataKey::PaidAt(period, tenant.clone()));
+        persistent.remove(&DataKey::Received(period, tenant.clone()));
+        persistent.remove(&DataKey::Covered(period, tenant.clone()));
+        persistent.remove(&DataKey::Refunded(period, tenant.clone()));
+    }
+}
+
+pub fn get_paid(env: &Env, period: u32, tenant: &Address) -> i128 {
+    let key = DataKey::Paid(period, tenant.clone());
+    match env.storage().persistent().get(&key) {
+        Some(paid) => {
+            extend_persistent(env, &key);
+            paid
+        }
+        None => 0,
+    }
+}
+
+/// Like [`get_paid`], without extending the entry's TTL.
+pub fn peek_paid(env: &Env, period: u32, tenant: &Address) -> i128 {
+    peek(env, &DataKey::Paid(period, tenant.clone())).unwrap_or(0)
+}
+
+pub fn set_paid(env: &Env, period: u32, tenant: &Address, amount: i128) {
+    let key = DataKey::Paid(period, tenant.clone());
+    env.storage().persistent().set(&key, &amount);
+    extend_persistent(env, &key);
+}
+
+pub fn get_late_fee(env: &Env, period: u32, tenant: &Address) -> i128 {
+    let key = DataKey::LateFee(period, tenant.clone());
+    match env.storage().persistent().get(&key) {
+        Some(fee) => {
+            extend_persistent(env, &key);
+            fee
+        }
+        None => 0,
+    }
+}
+
+/// Like [`get_late_fee`], without extending the entry's TTL.
+pub fn peek_late_fee(env: &Env, period: u32, tenant: &Address) -> i128 {
+    peek(env, &DataKey::LateFee(period, tenant.clone())).unwrap_or(0)
+}
+
+pub fn set_late_fee(env: &Env, period: u32, tenant: &Address, amount: i128) {
+    let key = DataKey::LateFee(period, tenant.clone());
+    env.storage().persistent().set(&key, &amount);
+    extend_persistent(env, &key);
+}
+
+pub fn get_covered(env: &Env, period: u32, tenant: &Address) -> Vec<u64> {
+    let key = DataKey::Covered(period, tenant.clone());
+    match env.storage().persistent().get(&key) {
+        Some(covered) => {
+            extend_persistent(env, &key);
+            covered
+        }
+        None => Vec::new(env),
+    }
+}
+
+/// Like [`get_covered`], without extending the entry's TTL.
+pub fn peek_covered(env: &Env, period: u32, tenant: &Address) -> Vec<u64> {
+    peek(env, &DataKey::Covered(period, tenant.clone())).unwrap_or_else(|| Vec::new(env))
+}
+
+pub fn set_covered(env: &Env, period: u32, tenant: &Address, covered: &Vec<u64>) {
+    let key = DataKey::Covered(period, tenant.clone());
+    env.storage().persistent().set(&key, covered);
+    extend_persistent(env, &key);
+}
+
+pub fn get_received(env: &Env, period: u32, tenant: &Address) -> i128 {
+    let key = DataKey::Received(period, tenant.clone());
+    match env.storage().persistent().get(&key) {
+        Some(received) => {
+            extend_persistent(env, &key);
+            received
+        }
+        None => 0,
+    }
+}
+
+pub fn set_received(env: &Env, period: u32, tenant: &Address, amount: i128) {
+    let key = DataKey::Received(period, tenant.clone());
+    env.storage().persistent().set(&key, &amount);
+    extend_persistent(env, &key);
+}
+
+/// Returns when `tenant` last paid towards `period`. Only views read it, so
+/// its TTL is not extended.
+pub fn peek_paid_at(env: &Env, period: u32, tenant: &Address) -> Option<u64> {
+    peek(env, &DataKey::PaidAt(period, tenant.clone()))
+}
+
+pub fn set_paid_at(env: &Env, period: u32, tenant: &Address, timestamp: u64) {
+    let key = DataKey::PaidAt(period, tenant.clone());
+    env.storage().persistent().set(&key, &timestamp);
+    extend_persistent(env, &key);
+}
+
+pub fn is_refunded(env: &Env, period: u32, tenant: &Address) -> bool {
+    let key = DataKey::Refunded(period, tenant.clone());
+    env.storage().persistent().has(&key)
+}
+
+pub fn set_refunded(env: &Env, period: u32, tenant: &Address) {
+    let key = DataKey::Refunded(period, tenant.clone());
+    env.storage().persistent().set(&key, &true);
+    extend_persistent(env, &key);
+}
+
+pub fn get_dispute(env: &Env, period: u32) -> Option<Dispute> {
+    let key = DataKey::Dispute(period);
+    let dispute = env.storage().persistent().get(&key);
+    if dispute.is_some() {
+        extend_persistent(env, &key);
+    }
+    dispute
+}
+
+/// Like [`get_dispute`], without extending the record's TTL.
+pub fn peek_dispute(env: &Env, period: u32) -> Option<Dispute> {
+    peek(env, &DataKey::Dispute(period))
+}
+
+pub fn set_dispute(env: &Env, dispute: &Dispute) {
+    let key = DataKey::Dispute(dispute.period);
+    env.storage().persistent().set(&key, dispute);
+    extend_persistent(env, &key);
+}
@@ -1,21 +1,4178 @@
 #![cfg(test)]
 
-use crate::testutils::setup_test_env;
-use soroban_sdk::symbol_short;
+extern crate std;
 
-#[test]
-fn test_hello() {
-    let (_env, _user, client) = setup_test_env();
-
-    let words = client.hello(&symbol_short!("Dev"));
-    assert_eq!(words, symbol_short!("Hello"));
-}
+use crate::storage;
+use crate::testutils::{
+    arbiter, create_oracle, create_six_decimal_token, create_token, default_terms, escrow_events,
+    event, fund_period, fund_security_deposit, mint, set_price, set_time, setup_escrow,
+    setup_escrow_with_token, setup_initialized_escrow, setup_initialized_escrow_with,
+    setup_test_env, Escrow, MONTH,
+};
+use crate::types::DAY;
+use crate::{
+    checked_add, transition, Arbitration, ContractStatus, CreditAppliedEvent, CreditWithdrawnEvent,
+    DailyFee, Deduction, DeductionsEvent, DepositedEvent, DisbursedEvent, Dispute,
+    DisputeOpenedEvent, DisputeResolvedEvent, DisputeStatus, Error, Fallback, InitializedEvent,
+    Installment, LateFee, LateFeeEvent, LateFeePolicy, Outcome, Payment, PayoutMode, PeriodRecord,
+    PeriodStatus, PriceFeed, Pricing, RefundedEvent, Schedule, SecurityDeposit,
+    SecurityFundedEvent, SecurityRefundedEvent, SecuritySettledEvent, SettlementStatus, Split,
+    StatusChangedEvent, Terms, VoteCastEvent, MAX_HISTORY_READS, MAX_TENANTS,
+};
+use soroban_sdk::{
+    testutils::{
+        storage::Persistent as _, Address as _, AuthorizedFunction, AuthorizedInvocation, Ledger,
+        MockAuth, MockAuthInvoke,
+    },
+    token::TokenClient,
+    vec, Address, BytesN, Env, IntoVal, Map, Symbol, Vec,
+};
 
 #[test]
 fn test_mock_environment() {
     let (env, user, _client) = setup_test_env();
-    
+
     // Verify user address generation and environment state
-    assert!(user.to_string().len() > 0);
+    assert!(!user.to_string().is_empty());
     assert_eq!(env.ledger().sequence(), 0);
 }
+
+#[test]
+fn test_initialize_stores_agreement() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+
+    let agreement = escrow.client.get_agreement();
+    assert_eq!(agreement.landlord, escrow.landlord);
+    assert_eq!(agreement.tenants, escrow.tenants);
+    assert_eq!(agreement.rent_amount, 1_000);
+    assert_eq!(agreement.token, escrow.token);
+    assert_eq!(agreement.payout, PayoutMode::Automatic);
+}
+
+#[test]
+fn test_initialize_requires_landlord_auth() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+
+    let auths = escrow.env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, escrow.landlord);
+}
+
+#[test]
+fn test_initialize_twice() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &500,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn test_initialize_without_tenants() {
+    let escrow = setup_escrow(0);
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &1_000,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_initialize_with_duplicate_tenant() {
+    let escrow = setup_escrow(0);
+    let tenant = Address::generate(&escrow.env);
+    let tenants = vec![&escrow.env, tenant.clone(), tenant];
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &tenants,
+            &1_000,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_initialize_with_landlord_as_tenant() {
+    let escrow = setup_escrow(0);
+    let tenants: Vec<Address> = vec![&escrow.env, escrow.landlord.clone()];
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &tenants,
+            &1_000,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_initialize_with_zero_rent() {
+    let escrow = setup_escrow(1);
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &0,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_initialize_with_negative_rent() {
+    let escrow = setup_escrow(1);
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &-1,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_get_agreement_before_initialize() {
+    let (_env, _user, client) = setup_test_env();
+    assert_eq!(client.try_get_agreement(), Err(Ok(Error::NotInitialized)));
+}
+
+#[test]
+fn test_deposit_moves_funds_into_escrow() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 700);
+
+    escrow.client.deposit(&tenant, &400);
+
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    assert_eq!(token.balance(&tenant), 300);
+    assert_eq!(token.balance(&escrow.client.address), 400);
+}
+
+#[test]
+fn test_deposit_records_amount_per_tenant() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 1_000);
+    mint(&escrow, &second, 1_000);
+
+    escrow.client.deposit(&first, &200);
+    escrow.client.deposit(&first, &100);
+    escrow.client.deposit(&second, &500);
+
+    assert_eq!(escrow.client.get_paid(&first), 300);
+    assert_eq!(escrow.client.get_paid(&second), 500);
+}
+
+#[test]
+fn test_deposit_requires_tenant_auth() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 500);
+
+    escrow.client.deposit(&tenant, &500);
+
+    assert_eq!(
+        escrow.env.auths(),
+        std::vec![(
+            tenant.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    escrow.client.address.clone(),
+                    Symbol::new(&escrow.env, "deposit"),
+                    (tenant.clone(), 500_i128).into_val(&escrow.env),
+                )),
+                sub_invocations: std::vec![AuthorizedInvocation {
+                    function: AuthorizedFunction::Contract((
+                        escrow.token.clone(),
+                        Symbol::new(&escrow.env, "transfer"),
+                        (tenant.clone(), escrow.client.address.clone(), 500_i128)
+                            .into_val(&escrow.env),
+                    )),
+                    sub_invocations: std::vec![],
+                }],
+            },
+        )]
+    );
+}
+
+#[test]
+fn test_deposit_from_stranger() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let stranger = Address::generate(&escrow.env);
+    mint(&escrow, &stranger, 500);
+
+    assert_eq!(
+        escrow.client.try_deposit(&stranger, &500),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_deposit_zero() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_deposit_without_funds() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &500),
+        Err(Ok(Error::InsufficientFunds))
+    );
+}
+
+#[test]
+fn test_deposit_before_initialize() {
+    let escrow = setup_escrow(1);
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &500),
+        Err(Ok(Error::NotInitialized))
+    );
+}
+
+#[test]
+fn test_views_before_any_deposit() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(escrow.client.get_paid(&tenant), 0);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 500);
+    assert_eq!(escrow.client.get_remaining(), 1_000);
+}
+
+#[test]
+fn test_views_track_partial_deposits() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 1_000);
+
+    escrow.client.deposit(&first, &200);
+
+    assert_eq!(escrow.client.get_balance(), 200);
+    assert_eq!(escrow.client.get_paid(&first), 200);
+    assert_eq!(escrow.client.get_outstanding(&first), 300);
+    assert_eq!(escrow.client.get_outstanding(&second), 500);
+    assert_eq!(escrow.client.get_remaining(), 800);
+}
+
+#[test]
+fn test_outstanding_never_goes_negative() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let first = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &first, 1_000);
+
+    escrow.client.deposit(&first, &700);
+
+    assert_eq!(escrow.client.get_outstanding(&first), 0);
+    assert_eq!(escrow.client.get_credit(&first), 200);
+    assert_eq!(escrow.client.get_remaining(), 500);
+}
+
+#[test]
+fn test_balance_ignores_direct_token_transfers() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    mint(&escrow, &escrow.client.address, 5_000);
+
+    assert_eq!(escrow.client.get_balance(), 0);
+}
+
+#[test]
+fn test_uneven_rent_assigns_remainder_to_first_tenant() {
+    let escrow = setup_initialized_escrow(3, 1_000);
+
+    let tenant = escrow.tenants.get(0).unwrap();
+    assert_eq!(escrow.client.get_outstanding(&tenant), 334);
+    let tenant = escrow.tenants.get(1).unwrap();
+    assert_eq!(escrow.client.get_outstanding(&tenant), 333);
+    let tenant = escrow.tenants.get(2).unwrap();
+    assert_eq!(escrow.client.get_outstanding(&tenant), 333);
+}
+
+#[test]
+fn test_outstanding_for_stranger() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    assert_eq!(
+        escrow
+            .client
+            .try_get_outstanding(&Address::generate(&escrow.env)),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_full_rent_is_disbursed_to_landlord() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 500);
+    mint(&escrow, &second, 500);
+
+    escrow.client.deposit(&first, &500);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    assert_eq!(token.balance(&escrow.landlord), 0);
+
+    escrow.client.deposit(&second, &500);
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(token.balance(&escrow.client.address), 0);
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(escrow.client.get_remaining(), 0);
+}
+
+#[test]
+fn test_disbursement_emits_event() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+
+    escrow.client.deposit(&tenant, &1_000);
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 0,
+                    rent: 1_000,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "disbursed",
+                &escrow.landlord,
+                DisbursedEvent {
+                    period: 0,
+                    amount: 1_000,
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Active,
+                    to: ContractStatus::Fulfilled,
+                },
+            ),
+        ]
+    );
+}
+
+/// Runs a two-period lease paid in the token made by `create`, cancelling it
+/// halfway through the second period.
+fn assert_lease_in_token(create: impl FnOnce(&Env) -> Address, decimals: u32) {
+    let escrow = setup_escrow_with_token(2, create);
+    let unit = 10_i128.pow(decimals);
+    let mut terms = default_terms(&escrow.env);
+    terms.schedule.periods = 2;
+    escrow.client.initialize(
+        &escrow.landlord,
+        &escrow.tenants,
+        &(1_500 * unit),
+        &escrow.token,
+        &terms,
+    );
+    assert_eq!(escrow.client.get_agreement().decimals, decimals);
+
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    fund_period(&escrow);
+    mint(&escrow, &tenant, 300 * unit);
+    escrow.client.deposit(&tenant, &(300 * unit));
+    escrow.client.cancel();
+
+    assert_eq!(token.balance(&escrow.landlord), 1_500 * unit);
+    assert_eq!(token.balance(&tenant), 300 * unit);
+    assert_eq!(token.balance(&escrow.client.address), 0);
+}
+
+#[test]
+fn test_lease_in_native_like_token() {
+    assert_lease_in_token(create_token, 7);
+}
+
+#[test]
+fn test_lease_in_six_decimal_token() {
+    assert_lease_in_token(create_six_decimal_token, 6);
+}
+
+#[test]
+fn test_initialize_emits_events() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_late_deposit_emits_fee_and_deposit_events() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.late_fee = LateFee {
+            grace: 0,
+            policy: LateFeePolicy::Flat(50),
+        }
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 650);
+
+    set_time(&escrow, DAY);
+    escrow.client.deposit(&tenant, &650);
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "late_fee",
+                &tenant,
+                LateFeeEvent { period: 0, fee: 50 },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 0,
+                    rent: 500,
+                    credit: 100,
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_excess_is_credited_to_depositing_tenant() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 500);
+    mint(&escrow, &second, 800);
+
+    escrow.client.deposit(&first, &500);
+    escrow.client.deposit(&second, &800);
+
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(token.balance(&escrow.client.address), 300);
+    assert_eq!(escrow.client.get_paid(&second), 500);
+    assert_eq!(escrow.client.get_credit(&second), 300);
+    assert_eq!(escrow.client.get_credit(&first), 0);
+    assert_eq!(escrow.client.get_balance(), 300);
+}
+
+#[test]
+fn test_credit_goes_to_next_period() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| terms.schedule.periods = 2);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 800);
+    mint(&escrow, &second, 500);
+
+    escrow.client.deposit(&first, &800);
+    assert_eq!(escrow.client.get_credit(&first), 300);
+    escrow.client.deposit(&second, &500);
+
+    assert_eq!(escrow.client.get_current_period(), 1);
+    assert_eq!(escrow.client.get_paid(&first), 300);
+    assert_eq!(escrow.client.get_credit(&first), 0);
+    assert_eq!(escrow.client.get_outstanding(&first), 200);
+    assert_eq!(escrow.client.get_balance(), 300);
+
+    let events = escrow_events(&escrow);
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "credit_applied",
+                &first,
+                CreditAppliedEvent {
+                    period: 1,
+                    rent: 300,
+                    credit: 0,
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_credit_can_fund_later_periods() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| terms.schedule.periods = 3);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 2_500);
+
+    escrow.client.deposit(&tenant, &2_500);
+
+    assert_eq!(token.balance(&escrow.landlord), 2_000);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Disbursed);
+    assert_eq!(escrow.client.get_current_period(), 2);
+    assert_eq!(escrow.client.get_paid(&tenant), 500);
+    assert_eq!(escrow.client.get_credit(&tenant), 0);
+}
+
+#[test]
+fn test_unused_credit_is_withdrawn_at_lease_end() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_200);
+    escrow.client.deposit(&tenant, &1_200);
+
+    escrow.client.withdraw_credit(&tenant);
+
+    assert_eq!(
+        escrow.env.auths(),
+        std::vec![(
+            tenant.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    escrow.client.address.clone(),
+                    Symbol::new(&escrow.env, "withdraw_credit"),
+                    (tenant.clone(),).into_val(&escrow.env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(token.balance(&tenant), 200);
+    assert_eq!(escrow.client.get_credit(&tenant), 0);
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(
+        escrow.client.try_withdraw_credit(&tenant),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_credit_stays_in_escrow_during_lease() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 700);
+    escrow.client.deposit(&tenant, &700);
+
+    assert_eq!(
+        escrow.client.try_withdraw_credit(&tenant),
+        Err(Ok(Error::InvalidState))
+    );
+
+    set_time(&escrow, MONTH);
+    escrow.client.withdraw_credit(&tenant);
+    escrow.client.claim_refund(&tenant);
+    assert_eq!(token.balance(&tenant), 700);
+}
+
+#[test]
+fn test_deposit_after_rent_collected() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 2_000);
+
+    escrow.client.deposit(&tenant, &1_000);
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &1_000),
+        Err(Ok(Error::InvalidState))
+    );
+}
+
+#[test]
+fn test_manual_payout_holds_rent_until_withdrawn() {
+    let escrow =
+        setup_initialized_escrow_with(2, 1_000, |_, terms| terms.payout = PayoutMode::Manual);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 500);
+    mint(&escrow, &second, 500);
+
+    escrow.client.deposit(&first, &500);
+    escrow.client.deposit(&second, &500);
+
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    assert_eq!(token.balance(&escrow.landlord), 0);
+    assert_eq!(escrow.client.get_balance(), 1_000);
+
+    escrow.client.withdraw();
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(token.balance(&escrow.client.address), 0);
+    assert_eq!(escrow.client.get_balance(), 0);
+}
+
+#[test]
+fn test_withdraw_requires_landlord_auth() {
+    let escrow =
+        setup_initialized_escrow_with(1, 1_000, |_, terms| terms.payout = PayoutMode::Manual);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+    escrow.client.deposit(&tenant, &1_000);
+
+    escrow.client.withdraw();
+
+    let auths = escrow.env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, escrow.landlord);
+    assert_eq!(
+        auths[0].1.function,
+        AuthorizedFunction::Contract((
+            escrow.client.address.clone(),
+            Symbol::new(&escrow.env, "withdraw"),
+            ().into_val(&escrow.env),
+        ))
+    );
+}
+
+#[test]
+fn test_withdraw_by_someone_else() {
+    let escrow =
+        setup_initialized_escrow_with(1, 1_000, |_, terms| terms.payout = PayoutMode::Manual);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+    escrow.client.deposit(&tenant, &1_000);
+
+    let result = escrow
+        .client
+        .mock_auths(&[MockAuth {
+            address: &tenant,
+            invoke: &MockAuthInvoke {
+                contract: &escrow.client.address,
+                fn_name: "withdraw",
+                args: ().into_val(&escrow.env),
+                sub_invokes: &[],
+            },
+        }])
+        .try_withdraw();
+
+    assert!(result.is_err());
+    assert_eq!(escrow.client.get_balance(), 1_000);
+}
+
+#[test]
+fn test_withdraw_before_rent_collected() {
+    let escrow =
+        setup_initialized_escrow_with(2, 1_000, |_, terms| terms.payout = PayoutMode::Manual);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 500);
+    escrow.client.deposit(&tenant, &500);
+
+    assert_eq!(
+        escrow.client.try_withdraw(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_withdraw_twice() {
+    let escrow =
+        setup_initialized_escrow_with(1, 1_000, |_, terms| terms.payout = PayoutMode::Manual);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+    escrow.client.deposit(&tenant, &1_000);
+
+    escrow.client.withdraw();
+    assert_eq!(
+        escrow.client.try_withdraw(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_withdraw_with_automatic_payout() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+    escrow.client.deposit(&tenant, &1_000);
+
+    assert_eq!(
+        escrow.client.try_withdraw(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+const STATUSES: [ContractStatus; 5] = [
+    ContractStatus::Inactive,
+    ContractStatus::Active,
+    ContractStatus::Fulfilled,
+    ContractStatus::Expired,
+    ContractStatus::Cancelled,
+];
+
+/// Drives a single-tenant escrow with a rent of 1 000 into `status` through
+/// its entrypoints.
+fn escrow_in(status: ContractStatus) -> Escrow {
+    if status == ContractStatus::Inactive {
+        return setup_escrow(1);
+    }
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    match status {
+        ContractStatus::Fulfilled => fund_period(&escrow),
+        ContractStatus::Expired => {
+            mint(&escrow, &tenant, 400);
+            escrow.client.deposit(&tenant, &400);
+            set_time(&escrow, MONTH);
+            escrow.client.claim_refund(&tenant);
+        }
+        ContractStatus::Cancelled => escrow.client.cancel(),
+        _ => {}
+    }
+    escrow
+}
+
+/// Calls the entrypoint that moves an escrow to `status`, returning the
+/// error it fails with.
+fn try_move_to(escrow: &Escrow, status: ContractStatus) -> Result<(), Error> {
+    let tenant = escrow.tenants.get(0).unwrap();
+    let result = match status {
+        ContractStatus::Inactive | ContractStatus::Active => escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &1_000,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        ContractStatus::Fulfilled => {
+            mint(escrow, &tenant, 1_000);
+            escrow.client.try_deposit(&tenant, &1_000)
+        }
+        ContractStatus::Expired => escrow.client.try_claim_refund(&tenant),
+        ContractStatus::Cancelled => escrow.client.try_cancel(),
+    };
+    match result {
+        Ok(_) => Ok(()),
+        Err(error) => Err(error.unwrap()),
+    }
+}
+
+#[test]
+fn test_entrypoints_make_every_valid_transition() {
+    let escrow = escrow_in(ContractStatus::Inactive);
+    assert_eq!(try_move_to(&escrow, ContractStatus::Active), Ok(()));
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+
+    for to in [
+        ContractStatus::Fulfilled,
+        ContractStatus::Expired,
+        ContractStatus::Cancelled,
+    ] {
+        let escrow = escrow_in(ContractStatus::Active);
+        if to == ContractStatus::Expired {
+            let tenant = escrow.tenants.get(0).unwrap();
+            mint(&escrow, &tenant, 400);
+            escrow.client.deposit(&tenant, &400);
+            set_time(&escrow, MONTH);
+        }
+
+        assert_eq!(try_move_to(&escrow, to), Ok(()), "Active -> {:?}", to);
+        assert_eq!(escrow.client.get_status(), to);
+        assert!(escrow_events(&escrow).contains(event(
+            &escrow,
+            "status_changed",
+            &escrow.client.address,
+            StatusChangedEvent {
+                from: ContractStatus::Active,
+                to,
+            },
+        )));
+    }
+}
+
+#[test]
+fn test_entrypoints_reject_every_invalid_transition() {
+    use ContractStatus::*;
+    for (from, to, error) in [
+        (Inactive, Fulfilled, Error::NotInitialized),
+        (Inactive, Expired, Error::NotInitialized),
+        (Inactive, Cancelled, Error::NotInitialized),
+        (Active, Active, Error::AlreadyInitialized),
+        (Fulfilled, Active, Error::AlreadyInitialized),
+        (Fulfilled, Fulfilled, Error::InvalidState),
+        (Fulfilled, Expired, Error::InvalidState),
+        (Fulfilled, Cancelled, Error::InvalidState),
+        (Expired, Active, Error::AlreadyInitialized),
+        (Expired, Fulfilled, Error::Expired),
+        (Expired, Expired, Error::ConditionNotMet),
+        (Expired, Cancelled, Error::InvalidState),
+        (Cancelled, Active, Error::AlreadyInitialized),
+        (Cancelled, Fulfilled, Error::InvalidState),
+        (Cancelled, Expired, Error::InvalidState),
+        (Cancelled, Cancelled, Error::InvalidState),
+    ] {
+        let escrow = escrow_in(from);
+
+        assert_eq!(
+            try_move_to(&escrow, to),
+            Err(error),
+            "{:?} -> {:?}",
+            from,
+            to
+        );
+        assert_eq!(escrow.client.get_status(), from);
+    }
+}
+
+#[test]
+fn test_valid_transitions_are_stored() {
+    for (from, to) in [
+        (ContractStatus::Active, ContractStatus::Fulfilled),
+        (ContractStatus::Active, ContractStatus::Expired),
+        (ContractStatus::Active, ContractStatus::Cancelled),
+    ] {
+        let escrow = setup_initialized_escrow(1, 1_000);
+        assert_eq!(escrow.client.get_status(), from);
+
+        let result = escrow
+            .env
+            .as_contract(&escrow.client.address, || transition(&escrow.env, to));
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(escrow.client.get_status(), to);
+    }
+}
+
+#[test]
+fn test_invalid_transitions_are_rejected() {
+    for from in STATUSES {
+        for to in STATUSES {
+            if from.can_transition_to(to) {
+                continue;
+            }
+            let escrow = setup_escrow(1);
+            escrow.env.as_contract(&escrow.client.address, || {
+                storage::set_status(&escrow.env, from);
+            });
+
+            let result = escrow
+                .env
+                .as_contract(&escrow.client.address, || transition(&escrow.env, to));
+
+            assert_eq!(result, Err(Error::InvalidState), "{:?} -> {:?}", from, to);
+            assert_eq!(escrow.client.get_status(), from);
+        }
+    }
+}
+
+#[test]
+fn test_status_is_inactive_before_initialize() {
+    let (_env, _user, client) = setup_test_env();
+    assert_eq!(client.get_status(), ContractStatus::Inactive);
+}
+
+#[test]
+fn test_initialize_activates_escrow() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+}
+
+#[test]
+fn test_partial_deposit_keeps_escrow_active() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 500);
+
+    escrow.client.deposit(&tenant, &500);
+
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+}
+
+#[test]
+fn test_collecting_full_rent_fulfills_escrow() {
+    for payout in [PayoutMode::Automatic, PayoutMode::Manual] {
+        let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| terms.payout = payout);
+        let tenant = escrow.tenants.get(0).unwrap();
+        mint(&escrow, &tenant, 1_000);
+
+        escrow.client.deposit(&tenant, &1_000);
+
+        assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+    }
+}
+
+#[test]
+fn test_withdraw_keeps_escrow_fulfilled() {
+    let escrow =
+        setup_initialized_escrow_with(1, 1_000, |_, terms| terms.payout = PayoutMode::Manual);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+    escrow.client.deposit(&tenant, &1_000);
+
+    escrow.client.withdraw();
+
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+}
+
+#[test]
+fn test_deposit_rejected_unless_active() {
+    for (status, error) in [
+        (ContractStatus::Fulfilled, Error::InvalidState),
+        (ContractStatus::Expired, Error::Expired),
+        (ContractStatus::Cancelled, Error::InvalidState),
+    ] {
+        let escrow = setup_initialized_escrow(1, 1_000);
+        let tenant = escrow.tenants.get(0).unwrap();
+        mint(&escrow, &tenant, 1_000);
+        escrow.env.as_contract(&escrow.client.address, || {
+            storage::set_status(&escrow.env, status);
+        });
+
+        let result = escrow.client.try_deposit(&tenant, &1_000);
+
+        assert_eq!(result, Err(Ok(error)), "deposit while {:?}", status);
+        assert_eq!(escrow.client.get_balance(), 0);
+    }
+}
+
+#[test]
+fn test_withdraw_from_cancelled_escrow() {
+    let escrow =
+        setup_initialized_escrow_with(1, 1_000, |_, terms| terms.payout = PayoutMode::Manual);
+    escrow.env.as_contract(&escrow.client.address, || {
+        storage::set_status(&escrow.env, ContractStatus::Cancelled);
+    });
+
+    assert_eq!(
+        escrow.client.try_withdraw(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_checked_add_reports_overflow() {
+    assert_eq!(checked_add(i128::MAX - 1, 1), Ok(i128::MAX));
+    assert_eq!(checked_add(i128::MAX, 1), Err(Error::Overflow));
+}
+
+#[test]
+fn test_error_codes_match_web_app() {
+    assert_eq!(Error::Unauthorized as u32, 1);
+    assert_eq!(Error::InsufficientFunds as u32, 2);
+    assert_eq!(Error::ConditionNotMet as u32, 3);
+    assert_eq!(Error::InvalidTenant as u32, 4);
+}
+
+#[test]
+fn test_equal_split_resolves_to_shares() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    assert_eq!(
+        escrow.client.get_agreement().shares,
+        vec![&escrow.env, 500_i128, 500]
+    );
+}
+
+#[test]
+fn test_basis_point_split() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        terms.split = Split::BasisPoints(vec![env, 6_000, 4_000])
+    });
+
+    let tenant = escrow.tenants.get(0).unwrap();
+    assert_eq!(escrow.client.get_outstanding(&tenant), 600);
+    let tenant = escrow.tenants.get(1).unwrap();
+    assert_eq!(escrow.client.get_outstanding(&tenant), 400);
+}
+
+#[test]
+fn test_basis_point_dust_goes_to_first_tenant() {
+    let escrow = setup_initialized_escrow_with(3, 1_001, |env, terms| {
+        terms.split = Split::BasisPoints(vec![env, 3_333, 3_333, 3_334])
+    });
+
+    let shares = escrow.client.get_agreement().shares;
+    assert_eq!(shares, vec![&escrow.env, 335_i128, 333, 333]);
+    assert_eq!(shares.iter().sum::<i128>(), 1_001);
+}
+
+#[test]
+fn test_amount_split() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        terms.split = Split::Amounts(vec![env, 700, 300])
+    });
+
+    let tenant = escrow.tenants.get(0).unwrap();
+    assert_eq!(escrow.client.get_outstanding(&tenant), 700);
+    let tenant = escrow.tenants.get(1).unwrap();
+    assert_eq!(escrow.client.get_outstanding(&tenant), 300);
+}
+
+#[test]
+fn test_invalid_splits_are_rejected() {
+    let escrow = setup_escrow(2);
+    let env = &escrow.env;
+    for split in [
+        Split::BasisPoints(vec![env, 10_000]),
+        Split::BasisPoints(vec![env, 5_000, 4_000]),
+        Split::BasisPoints(vec![env, 10_000, 0]),
+        Split::Amounts(vec![env, 700, 200]),
+        Split::Amounts(vec![env, 1_100, -100]),
+        Split::Amounts(vec![env, 1_000]),
+    ] {
+        assert_eq!(
+            escrow.client.try_initialize(
+                &escrow.landlord,
+                &escrow.tenants,
+                &1_000,
+                &escrow.token,
+                &Terms {
+                    split: split.clone(),
+                    ..default_terms(env)
+                },
+            ),
+            Err(Ok(Error::InvalidSplit)),
+            "{:?}",
+            split
+        );
+    }
+}
+
+#[test]
+fn test_split_too_fine_for_rent_is_rejected() {
+    let escrow = setup_escrow(3);
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &2,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::InvalidSplit))
+    );
+}
+
+#[test]
+fn test_deposit_is_tracked_against_own_share() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        terms.split = Split::Amounts(vec![env, 700, 300])
+    });
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 1_000);
+    mint(&escrow, &second, 300);
+
+    escrow.client.deposit(&first, &1_000);
+
+    assert_eq!(escrow.client.get_paid(&first), 700);
+    assert_eq!(escrow.client.get_credit(&first), 300);
+    assert_eq!(escrow.client.get_remaining(), 300);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+
+    escrow.client.deposit(&second, &300);
+
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+    assert_eq!(escrow.client.get_balance(), 300);
+}
+
+#[test]
+fn test_period_due_dates_follow_schedule() {
+    let escrow = setup_escrow(1);
+    set_time(&escrow, 5_000);
+    let mut terms = default_terms(&escrow.env);
+    terms.schedule.periods = 3;
+    escrow.client.initialize(
+        &escrow.landlord,
+        &escrow.tenants,
+        &1_000,
+        &escrow.token,
+        &terms,
+    );
+
+    for index in 0..3 {
+        let period = escrow.client.get_period(&index);
+        assert_eq!(period.due_date, 5_000 + index as u64 * MONTH);
+        assert_eq!(period.collected, 0);
+        assert_eq!(period.status, PeriodStatus::Open);
+    }
+    assert_eq!(
+        escrow.client.try_get_period(&3),
+        Err(Ok(Error::InvalidPeriod))
+    );
+}
+
+#[test]
+fn test_invalid_schedules_are_rejected() {
+    let escrow = setup_escrow(1);
+    let env = &escrow.env;
+    for schedule in [
+        Schedule {
+            start: 0,
+            interval: 0,
+            periods: 12,
+        },
+        Schedule {
+            start: 0,
+            interval: MONTH,
+            periods: 0,
+        },
+        Schedule {
+            start: u64::MAX - MONTH,
+            interval: MONTH,
+            periods: 2,
+        },
+    ] {
+        assert_eq!(
+            escrow.client.try_initialize(
+                &escrow.landlord,
+                &escrow.tenants,
+                &1_000,
+                &escrow.token,
+                &Terms {
+                    schedule: schedule.clone(),
+                    ..default_terms(env)
+                },
+            ),
+            Err(Ok(Error::InvalidSchedule)),
+            "{:?}",
+            schedule
+        );
+    }
+}
+
+#[test]
+fn test_funded_period_rolls_over_to_next() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| terms.schedule.periods = 3);
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    fund_period(&escrow);
+
+    assert_eq!(escrow.client.get_current_period(), 1);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+    let period = escrow.client.get_period(&0);
+    assert_eq!(period.collected, 1_000);
+    assert_eq!(period.status, PeriodStatus::Disbursed);
+    assert_eq!(escrow.client.get_paid(&tenant), 0);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 500);
+    assert_eq!(escrow.client.get_remaining(), 1_000);
+}
+
+#[test]
+fn test_each_period_is_paid_out_separately() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| terms.schedule.periods = 3);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+
+    for index in 0..3 {
+        assert_eq!(escrow.client.get_current_period(), index);
+        fund_period(&escrow);
+        assert_eq!(token.balance(&escrow.landlord), 1_000 * (index as i128 + 1));
+        assert_eq!(
+            escrow.client.get_period(&index).status,
+            PeriodStatus::Disbursed
+        );
+    }
+
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+    assert_eq!(escrow.client.get_current_period(), 2);
+    assert_eq!(escrow.client.get_balance(), 0);
+}
+
+#[test]
+fn test_manual_withdraw_pays_every_funded_period() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.schedule.periods = 3;
+    });
+
+    fund_period(&escrow);
+    fund_period(&escrow);
+
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Funded);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Funded);
+    assert_eq!(escrow.client.get_balance(), 2_000);
+
+    escrow.client.withdraw();
+
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    assert_eq!(token.balance(&escrow.landlord), 2_000);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Disbursed);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Disbursed);
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(
+        escrow.client.try_withdraw(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    fund_period(&escrow);
+    escrow.client.withdraw();
+
+    assert_eq!(token.balance(&escrow.landlord), 3_000);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+}
+
+#[test]
+fn test_escrow_expires_at_period_deadline() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| terms.schedule.periods = 2);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+    escrow.client.deposit(&tenant, &400);
+
+    set_time(&escrow, MONTH - 1);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+
+    set_time(&escrow, MONTH);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Expired);
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &600),
+        Err(Ok(Error::Expired))
+    );
+}
+
+#[test]
+fn test_later_period_expires_at_its_own_deadline() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| terms.schedule.periods = 2);
+    fund_period(&escrow);
+
+    set_time(&escrow, 2 * MONTH - 1);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+
+    set_time(&escrow, 2 * MONTH);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Expired);
+}
+
+#[test]
+fn test_fulfilled_escrow_does_not_expire() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    fund_period(&escrow);
+
+    set_time(&escrow, 10 * MONTH);
+
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+}
+
+#[test]
+fn test_tenants_claim_back_expired_period_deposits() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| terms.schedule.periods = 3);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    fund_period(&escrow);
+    mint(&escrow, &first, 300);
+    escrow.client.deposit(&first, &300);
+
+    set_time(&escrow, 2 * MONTH);
+    escrow.client.claim_refund(&first);
+
+    assert_eq!(token.balance(&first), 300);
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Expired);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Disbursed);
+    assert_eq!(
+        escrow.client.try_claim_refund(&first),
+        Err(Ok(Error::ConditionNotMet))
+    );
+    assert_eq!(
+        escrow.client.try_claim_refund(&second),
+        Err(Ok(Error::ConditionNotMet))
+    );
+    escrow.env.as_contract(&escrow.client.address, || {
+        assert_eq!(storage::get_status(&escrow.env), ContractStatus::Expired);
+    });
+}
+
+#[test]
+fn test_claim_refund_requires_expiry() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+
+    assert_eq!(
+        escrow.client.try_claim_refund(&tenant),
+        Err(Ok(Error::InvalidState))
+    );
+
+    fund_period(&escrow);
+    set_time(&escrow, 2 * MONTH);
+
+    assert_eq!(
+        escrow.client.try_claim_refund(&tenant),
+        Err(Ok(Error::InvalidState))
+    );
+}
+
+#[test]
+fn test_claim_refund_from_stranger() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    set_time(&escrow, MONTH);
+
+    assert_eq!(
+        escrow
+            .client
+            .try_claim_refund(&Address::generate(&escrow.env)),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_claim_refund_requires_tenant_auth() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+    set_time(&escrow, MONTH);
+
+    escrow.client.claim_refund(&tenant);
+
+    assert_eq!(
+        escrow.env.auths(),
+        std::vec![(
+            tenant.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    escrow.client.address.clone(),
+                    Symbol::new(&escrow.env, "claim_refund"),
+                    (tenant.clone(),).into_val(&escrow.env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+}
+
+#[test]
+fn test_refund_emits_event() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+    set_time(&escrow, MONTH);
+
+    escrow.client.claim_refund(&tenant);
+
+    // The expiry is recorded before the refund goes out.
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 0,
+                    rent: 400,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Active,
+                    to: ContractStatus::Expired,
+                },
+            ),
+            event(
+                &escrow,
+                "refunded",
+                &tenant,
+                RefundedEvent {
+                    period: 0,
+                    amount: 400,
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_funded_periods_stay_withdrawable_after_expiry() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.schedule.periods = 2;
+    });
+    fund_period(&escrow);
+
+    set_time(&escrow, 2 * MONTH);
+    escrow.client.withdraw();
+
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Expired);
+}
+
+#[test]
+fn test_landlord_alone_can_cancel_before_any_payment() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+
+    let result = escrow
+        .client
+        .mock_auths(&[MockAuth {
+            address: &escrow.landlord,
+            invoke: &MockAuthInvoke {
+                contract: &escrow.client.address,
+                fn_name: "cancel",
+                args: ().into_val(&escrow.env),
+                sub_invokes: &[],
+            },
+        }])
+        .try_cancel();
+
+    assert_eq!(result, Ok(Ok(())));
+    assert_eq!(escrow.client.get_status(), ContractStatus::Cancelled);
+}
+
+#[test]
+fn test_cancel_after_payment_needs_every_tenant() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 200);
+    escrow.client.deposit(&tenant, &200);
+
+    let result = escrow
+        .client
+        .mock_auths(&[MockAuth {
+            address: &escrow.landlord,
+            invoke: &MockAuthInvoke {
+                contract: &escrow.client.address,
+                fn_name: "cancel",
+                args: ().into_val(&escrow.env),
+                sub_invokes: &[],
+            },
+        }])
+        .try_cancel();
+
+    assert!(result.is_err());
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+    assert_eq!(escrow.client.get_balance(), 200);
+}
+
+#[test]
+fn test_cancel_requires_landlord_and_tenant_auth() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 200);
+    escrow.client.deposit(&tenant, &200);
+
+    escrow.client.cancel();
+
+    let invocation = |address: &Address| {
+        (
+            address.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    escrow.client.address.clone(),
+                    Symbol::new(&escrow.env, "cancel"),
+                    ().into_val(&escrow.env),
+                )),
+                sub_invocations: std::vec![],
+            },
+        )
+    };
+    assert_eq!(
+        escrow.env.auths(),
+        std::vec![
+            invocation(&escrow.landlord),
+            invocation(&escrow.tenants.get(0).unwrap()),
+            invocation(&escrow.tenants.get(1).unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn test_cancel_refunds_undisbursed_deposits() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.schedule.periods = 3;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    fund_period(&escrow);
+    mint(&escrow, &first, 700);
+    mint(&escrow, &second, 100);
+    escrow.client.deposit(&first, &700);
+    escrow.client.deposit(&second, &100);
+
+    escrow.client.cancel();
+
+    assert_eq!(token.balance(&first), 1_200);
+    assert_eq!(token.balance(&second), 600);
+    assert_eq!(token.balance(&escrow.landlord), 0);
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(escrow.client.get_credit(&first), 0);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Cancelled);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Cancelled);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Cancelled);
+    assert_eq!(
+        escrow.client.try_withdraw(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_cancel_keeps_disbursed_rent_with_landlord() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.schedule.periods = 3;
+        terms.security_deposit = 400;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    let other = escrow.tenants.get(1).unwrap();
+    fund_security_deposit(&escrow);
+    fund_period(&escrow);
+    mint(&escrow, &tenant, 700);
+    escrow.client.deposit(&tenant, &700);
+
+    escrow.client.cancel();
+
+    assert_eq!(token.balance(&tenant), 900);
+    assert_eq!(token.balance(&other), 200);
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Disbursed);
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "security_funded",
+                &tenant,
+                SecurityFundedEvent { amount: 200 },
+            ),
+            event(
+                &escrow,
+                "security_funded",
+                &other,
+                SecurityFundedEvent { amount: 200 },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 0,
+                    rent: 500,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &other,
+                DepositedEvent {
+                    period: 0,
+                    rent: 500,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "disbursed",
+                &escrow.landlord,
+                DisbursedEvent {
+                    period: 0,
+                    amount: 1_000,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 1,
+                    rent: 500,
+                    credit: 200,
+                },
+            ),
+            event(
+                &escrow,
+                "refunded",
+                &tenant,
+                RefundedEvent {
+                    period: 1,
+                    amount: 500,
+                },
+            ),
+            event(
+                &escrow,
+                "credit_withdrawn",
+                &tenant,
+                CreditWithdrawnEvent { amount: 200 },
+            ),
+            event(
+                &escrow,
+                "security_refunded",
+                &tenant,
+                SecurityRefundedEvent { amount: 200 },
+            ),
+            event(
+                &escrow,
+                "security_refunded",
+                &other,
+                SecurityRefundedEvent { amount: 200 },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Active,
+                    to: ContractStatus::Cancelled,
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_cancel_rejected_unless_active() {
+    let fulfilled = setup_initialized_escrow(1, 1_000);
+    fund_period(&fulfilled);
+    assert_eq!(fulfilled.client.try_cancel(), Err(Ok(Error::InvalidState)));
+
+    let expired = setup_initialized_escrow(1, 1_000);
+    set_time(&expired, MONTH);
+    assert_eq!(expired.client.try_cancel(), Err(Ok(Error::InvalidState)));
+
+    let cancelled = setup_initialized_escrow(1, 1_000);
+    cancelled.client.cancel();
+    assert_eq!(cancelled.client.try_cancel(), Err(Ok(Error::InvalidState)));
+}
+
+#[test]
+fn test_no_late_fee_within_grace_period() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.late_fee = LateFee {
+            grace: 3 * DAY,
+            policy: LateFeePolicy::Flat(50),
+        }
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    set_time(&escrow, 3 * DAY);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 0);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 1_000);
+
+    set_time(&escrow, 3 * DAY + 1);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 50);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 1_050);
+}
+
+#[test]
+fn test_late_fee_is_charged_to_late_tenant_only() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.late_fee = LateFee {
+            grace: DAY,
+            policy: LateFeePolicy::Flat(50),
+        }
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let punctual = escrow.tenants.get(0).unwrap();
+    let late = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &punctual, 500);
+    mint(&escrow, &late, 550);
+    escrow.client.deposit(&punctual, &500);
+
+    set_time(&escrow, 2 * DAY);
+    assert_eq!(escrow.client.get_late_fee(&punctual), 0);
+    assert_eq!(escrow.client.get_late_fee(&late), 50);
+    assert_eq!(escrow.client.get_outstanding(&punctual), 0);
+    assert_eq!(escrow.client.get_outstanding(&late), 550);
+
+    escrow.client.deposit(&late, &550);
+
+    assert_eq!(token.balance(&escrow.landlord), 1_050);
+    assert_eq!(escrow.client.get_balance(), 0);
+    let period = escrow.client.get_period(&0);
+    assert_eq!(period.collected, 1_000);
+    assert_eq!(period.late_fees, 50);
+    assert_eq!(period.status, PeriodStatus::Disbursed);
+}
+
+#[test]
+fn test_late_fee_is_taken_before_share() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.late_fee = LateFee {
+            grace: 0,
+            policy: LateFeePolicy::Flat(50),
+        }
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 600);
+    set_time(&escrow, DAY);
+
+    escrow.client.deposit(&tenant, &30);
+    assert_eq!(escrow.client.get_paid(&tenant), 0);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 520);
+
+    escrow.client.deposit(&tenant, &570);
+    assert_eq!(escrow.client.get_paid(&tenant), 500);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 0);
+    assert_eq!(escrow.client.get_credit(&tenant), 50);
+    assert_eq!(escrow.client.get_period(&0).late_fees, 50);
+}
+
+#[test]
+fn test_percentage_late_fee_applies_to_tenant_share() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        terms.split = Split::Amounts(vec![env, 800, 200]);
+        terms.late_fee = LateFee {
+            grace: 0,
+            policy: LateFeePolicy::Percentage(500),
+        }
+    });
+    set_time(&escrow, 1);
+
+    assert_eq!(
+        escrow.client.get_late_fee(&escrow.tenants.get(0).unwrap()),
+        40
+    );
+    assert_eq!(
+        escrow.client.get_late_fee(&escrow.tenants.get(1).unwrap()),
+        10
+    );
+}
+
+#[test]
+fn test_daily_late_fee_accrues_up_to_cap() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.late_fee = LateFee {
+            grace: DAY,
+            policy: LateFeePolicy::Daily(DailyFee { rate: 10, cap: 35 }),
+        }
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    for (time, fee) in [
+        (DAY, 0),
+        (DAY + 1, 10),
+        (2 * DAY, 10),
+        (2 * DAY + 1, 20),
+        (3 * DAY + 1, 30),
+        (10 * DAY, 35),
+    ] {
+        set_time(&escrow, time);
+        assert_eq!(escrow.client.get_late_fee(&tenant), fee, "at {}", time);
+    }
+}
+
+#[test]
+fn test_daily_late_fee_stops_once_share_is_paid() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.late_fee = LateFee {
+            grace: 0,
+            policy: LateFeePolicy::Daily(DailyFee {
+                rate: 10,
+                cap: 1_000,
+            }),
+        }
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_000);
+
+    set_time(&escrow, DAY);
+    escrow.client.deposit(&tenant, &100);
+    assert_eq!(escrow.client.get_paid(&tenant), 90);
+
+    set_time(&escrow, 3 * DAY);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 30);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 430);
+    escrow.client.deposit(&tenant, &430);
+
+    set_time(&escrow, 6 * DAY);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 30);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 0);
+    assert_eq!(escrow.client.get_period(&0).late_fees, 30);
+    assert_eq!(escrow.client.get_balance(), 530);
+}
+
+#[test]
+fn test_late_fee_resets_for_next_period() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.schedule.periods = 2;
+        terms.late_fee = LateFee {
+            grace: 0,
+            policy: LateFeePolicy::Flat(50),
+        }
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 1_050);
+
+    set_time(&escrow, DAY);
+    escrow.client.deposit(&tenant, &1_050);
+
+    assert_eq!(escrow.client.get_current_period(), 1);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 0);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 1_000);
+}
+
+/// A plan that splits a share into two halves, due at the start of the
+/// period and two weeks into it.
+fn biweekly(env: &Env) -> Vec<Installment> {
+    vec![
+        env,
+        Installment {
+            offset: 0,
+            bps: 5_000,
+        },
+        Installment {
+            offset: 14 * DAY,
+            bps: 5_000,
+        },
+    ]
+}
+
+/// A single-tenant escrow for 1 000 with the given late fee policy and a
+/// biweekly installment plan agreed with its tenant.
+fn installment_escrow(policy: LateFeePolicy) -> Escrow {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.late_fee = LateFee { grace: 0, policy }
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    escrow
+        .client
+        .set_installments(&tenant, &biweekly(&escrow.env));
+    mint(&escrow, &tenant, 2_000);
+    escrow
+}
+
+#[test]
+fn test_installment_plan_needs_landlord_and_tenant() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    let plan = biweekly(&escrow.env);
+
+    escrow.client.set_installments(&tenant, &plan);
+
+    let invocation = AuthorizedInvocation {
+        function: AuthorizedFunction::Contract((
+            escrow.client.address.clone(),
+            Symbol::new(&escrow.env, "set_installments"),
+            (tenant.clone(), plan.clone()).into_val(&escrow.env),
+        )),
+        sub_invocations: std::vec![],
+    };
+    assert_eq!(
+        escrow.env.auths(),
+        std::vec![
+            (escrow.landlord.clone(), invocation.clone()),
+            (tenant.clone(), invocation),
+        ]
+    );
+    assert_eq!(escrow.client.get_installments(&tenant), plan);
+}
+
+#[test]
+fn test_invalid_installment_plans_are_rejected() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let env = &escrow.env;
+    let tenant = escrow.tenants.get(0).unwrap();
+    let installment = |offset, bps| Installment { offset, bps };
+    for plan in [
+        vec![env, installment(0, 5_000)],
+        vec![env, installment(0, 10_000), installment(DAY, 0)],
+        vec![env, installment(DAY, 5_000), installment(DAY, 5_000)],
+        vec![env, installment(0, 5_000), installment(MONTH, 5_000)],
+    ] {
+        assert_eq!(
+            escrow.client.try_set_installments(&tenant, &plan),
+            Err(Ok(Error::InvalidSchedule)),
+            "{:?}",
+            plan
+        );
+    }
+    assert_eq!(
+        escrow
+            .client
+            .try_set_installments(&Address::generate(env), &biweekly(env)),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_installments_paid_on_time_carry_no_fee() {
+    let escrow = installment_escrow(LateFeePolicy::Flat(50));
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    escrow.client.deposit(&tenant, &500);
+    set_time(&escrow, 14 * DAY);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 0);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 500);
+
+    escrow.client.deposit(&tenant, &500);
+    assert_eq!(escrow.client.get_period(&0).late_fees, 0);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+}
+
+#[test]
+fn test_each_missed_installment_is_charged() {
+    let escrow = installment_escrow(LateFeePolicy::Flat(50));
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    set_time(&escrow, DAY);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 50);
+    escrow.client.deposit(&tenant, &550);
+    assert_eq!(escrow.client.get_paid(&tenant), 500);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 500);
+
+    set_time(&escrow, 15 * DAY);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 100);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 550);
+
+    escrow.client.deposit(&tenant, &550);
+    assert_eq!(escrow.client.get_period(&0).late_fees, 100);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+}
+
+#[test]
+fn test_daily_fee_stops_once_installment_is_paid() {
+    let escrow = installment_escrow(LateFeePolicy::Daily(DailyFee {
+        rate: 10,
+        cap: 1_000,
+    }));
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    set_time(&escrow, 3 * DAY);
+    escrow.client.deposit(&tenant, &530);
+    assert_eq!(escrow.client.get_paid(&tenant), 500);
+
+    set_time(&escrow, 20 * DAY);
+    assert_eq!(escrow.client.get_late_fee(&tenant), 30 + 60);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 560);
+}
+
+#[test]
+fn test_installment_plan_is_fixed_once_tenant_pays() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 200);
+    escrow.client.deposit(&tenant, &200);
+
+    assert_eq!(
+        escrow
+            .client
+            .try_set_installments(&tenant, &biweekly(&escrow.env)),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_history_reports_each_tenants_payments() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.schedule.periods = 3;
+        terms.late_fee = LateFee {
+            grace: DAY,
+            policy: LateFeePolicy::Flat(50),
+        };
+    });
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 700);
+    mint(&escrow, &second, 550);
+
+    set_time(&escrow, DAY);
+    escrow.client.deposit(&first, &500);
+    set_time(&escrow, 2 * DAY);
+    escrow.client.deposit(&second, &550);
+    set_time(&escrow, MONTH);
+    escrow.client.deposit(&first, &200);
+
+    let payment = |tenant: &Address, paid, late_fee, paid_at| Payment {
+        tenant: tenant.clone(),
+        paid,
+        late_fee,
+        paid_at,
+    };
+    assert_eq!(
+        escrow.client.get_history(&0, &10),
+        vec![
+            &escrow.env,
+            PeriodRecord {
+                period: 0,
+                due_date: 0,
+                payments: vec![
+                    &escrow.env,
+                    payment(&first, 500, 0, Some(DAY)),
+                    payment(&second, 500, 50, Some(2 * DAY)),
+                ],
+                late_fees: 50,
+                status: PeriodStatus::Disbursed,
+                paid_out_at: Some(2 * DAY),
+            },
+            PeriodRecord {
+                period: 1,
+                due_date: MONTH,
+                payments: vec![
+                    &escrow.env,
+                    payment(&first, 200, 0, Some(MONTH)),
+                    payment(&second, 0, 0, None),
+                ],
+                late_fees: 0,
+                status: PeriodStatus::Open,
+                paid_out_at: None,
+            },
+            PeriodRecord {
+                period: 2,
+                due_date: 2 * MONTH,
+                payments: vec![
+                    &escrow.env,
+                    payment(&first, 0, 0, None),
+                    payment(&second, 0, 0, None),
+                ],
+                late_fees: 0,
+                status: PeriodStatus::Open,
+                paid_out_at: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_history_is_paginated() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| terms.schedule.periods = 24);
+    let page_size = escrow.client.get_agreement().history_page();
+    assert_eq!(page_size, 9);
+
+    let page = escrow.client.get_history(&0, &100);
+    assert_eq!(page.len(), page_size);
+    assert_eq!(page.get(0).unwrap().period, 0);
+
+    let page = escrow.client.get_history(&page_size, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(1).unwrap().period, page_size + 1);
+
+    let page = escrow.client.get_history(&20, &page_size);
+    assert_eq!(page.len(), 4);
+    assert_eq!(page.get(3).unwrap().due_date, 23 * MONTH);
+
+    assert_eq!(escrow.client.get_history(&0, &0).len(), 0);
+    assert_eq!(
+        escrow.client.try_get_history(&24, &1),
+        Err(Ok(Error::InvalidPeriod))
+    );
+}
+
+#[test]
+fn test_history_page_stays_within_read_limit() {
+    for tenant_count in 1..=MAX_TENANTS {
+        let escrow = setup_initialized_escrow_with(tenant_count, 1_100, |_, terms| {
+            terms.schedule.periods = 24
+        });
+        let page = escrow.client.get_history(&0, &24);
+        let reads = page.len() * (1 + 3 * tenant_count);
+
+        assert!(!page.is_empty(), "{} tenants", tenant_count);
+        assert!(reads <= MAX_HISTORY_READS, "{} tenants", tenant_count);
+    }
+
+    let escrow = setup_escrow(MAX_TENANTS + 1);
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &1_200,
+            &escrow.token,
+            &default_terms(&escrow.env),
+        ),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+#[test]
+fn test_history_does_not_extend_ttl() {
+    let escrow = installment_escrow(LateFeePolicy::Flat(50));
+    let tenant = escrow.tenants.get(0).unwrap();
+    set_time(&escrow, DAY);
+    escrow.client.deposit(&tenant, &550);
+    let ttl = |key: storage::DataKey| {
+        escrow.env.as_contract(&escrow.client.address, || {
+            escrow.env.storage().persistent().get_ttl(&key)
+        })
+    };
+    let advance = |days: u32| {
+        escrow.env.ledger().with_mut(|ledger| {
+            ledger.sequence_number += days * 17_280;
+        });
+        escrow.env.as_contract(&escrow.client.address, || {
+            storage::extend_instance(&escrow.env);
+        });
+    };
+    advance(20);
+    advance(20);
+    let keys = [
+        storage::DataKey::Period(0),
+        storage::DataKey::Paid(0, tenant.clone()),
+        storage::DataKey::LateFee(0, tenant.clone()),
+        storage::DataKey::Covered(0, tenant.clone()),
+    ];
+    let before = keys.clone().map(&ttl);
+
+    escrow.client.get_history(&0, &1);
+    escrow.client.get_period(&0);
+    escrow.client.get_paid(&tenant);
+    escrow.client.get_remaining();
+    escrow.client.get_late_fee(&tenant);
+    escrow.client.get_outstanding(&tenant);
+
+    assert_eq!(keys.map(ttl), before);
+}
+
+#[test]
+fn test_history_records_arbitrated_payout() {
+    let (escrow, arbiters) = disputed_escrow(1, 1);
+    let tenant = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &tenant, 500);
+    escrow.client.deposit(&tenant, &500);
+    set_time(&escrow, 3 * DAY);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Resolved(300));
+
+    let record = escrow.client.get_history(&0, &1).get(0).unwrap();
+    assert_eq!(record.status, PeriodStatus::Arbitrated);
+    assert_eq!(record.paid_out_at, Some(3 * DAY));
+    assert_eq!(record.payments.get(0).unwrap().paid, 500);
+}
+
+#[test]
+fn test_invalid_late_fee_policies_are_rejected() {
+    let escrow = setup_escrow(1);
+    for policy in [
+        LateFeePolicy::Flat(-1),
+        LateFeePolicy::Percentage(10_001),
+        LateFeePolicy::Daily(DailyFee { rate: -1, cap: 10 }),
+        LateFeePolicy::Daily(DailyFee { rate: 1, cap: -10 }),
+    ] {
+        assert_eq!(
+            escrow.client.try_initialize(
+                &escrow.landlord,
+                &escrow.tenants,
+                &1_000,
+                &escrow.token,
+                &Terms {
+                    late_fee: LateFee {
+                        grace: 0,
+                        policy: policy.clone(),
+                    },
+                    ..default_terms(&escrow.env)
+                },
+            ),
+            Err(Ok(Error::InvalidAmount)),
+            "{:?}",
+            policy
+        );
+    }
+}
+
+#[test]
+fn test_refund_includes_late_fees() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.late_fee = LateFee {
+            grace: 0,
+            policy: LateFeePolicy::Flat(50),
+        }
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 300);
+    set_time(&escrow, DAY);
+    escrow.client.deposit(&tenant, &300);
+
+    set_time(&escrow, MONTH);
+    escrow.client.claim_refund(&tenant);
+
+    assert_eq!(token.balance(&tenant), 300);
+    assert_eq!(escrow.client.get_balance(), 0);
+}
+
+#[test]
+fn test_security_deposit_is_divided_like_the_rent() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        terms.split = Split::Amounts(vec![env, 700, 300]);
+        terms.security_deposit = 1_501;
+    });
+
+    let agreement = escrow.client.get_agreement();
+    assert_eq!(agreement.security_deposit, 1_501);
+    assert_eq!(agreement.security_shares, vec![&escrow.env, 1_051, 450]);
+}
+
+#[test]
+fn test_rent_is_not_taken_before_security_deposit() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.security_deposit = 2_000;
+    });
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 1_500);
+    escrow.client.fund_security_deposit(&first);
+
+    assert_eq!(
+        escrow.client.try_deposit(&first, &500),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    mint(&escrow, &second, 1_000);
+    escrow.client.fund_security_deposit(&second);
+    escrow.client.deposit(&first, &500);
+
+    assert_eq!(escrow.client.get_paid(&first), 500);
+}
+
+#[test]
+fn test_security_deposit_is_held_apart_from_rent() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.security_deposit = 2_000;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+
+    fund_security_deposit(&escrow);
+
+    assert_eq!(escrow.client.get_security_paid(&tenant), 1_000);
+    assert_eq!(
+        escrow.client.get_security_deposit(),
+        SecurityDeposit {
+            amount: 2_000,
+            held: 2_000,
+            locked_until: MONTH,
+        }
+    );
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(escrow.client.get_remaining(), 1_000);
+
+    fund_period(&escrow);
+    assert_eq!(escrow.client.get_balance(), 1_000);
+    escrow.client.withdraw();
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(token.balance(&escrow.client.address), 2_000);
+    assert_eq!(escrow.client.get_security_deposit().held, 2_000);
+}
+
+#[test]
+fn test_security_deposit_is_funded_once() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.security_deposit = 1_000;
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    fund_security_deposit(&escrow);
+    mint(&escrow, &tenant, 1_000);
+
+    assert_eq!(
+        escrow.client.try_fund_security_deposit(&tenant),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    let without = setup_initialized_escrow(1, 1_000);
+    assert_eq!(
+        without
+            .client
+            .try_fund_security_deposit(&without.tenants.get(0).unwrap()),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_security_deposit_without_funds() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.security_deposit = 1_000;
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 999);
+
+    assert_eq!(
+        escrow.client.try_fund_security_deposit(&tenant),
+        Err(Ok(Error::InsufficientFunds))
+    );
+}
+
+#[test]
+fn test_security_deposit_is_locked_until_lease_end() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.schedule.periods = 2;
+        terms.security_deposit = 1_500;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    fund_security_deposit(&escrow);
+    fund_period(&escrow);
+    fund_period(&escrow);
+
+    set_time(&escrow, 2 * MONTH - 1);
+    assert_eq!(
+        escrow.client.try_settle_security_deposit(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    set_time(&escrow, 2 * MONTH + 7 * DAY - 1);
+    assert_eq!(
+        escrow.client.try_settle_security_deposit(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    set_time(&escrow, 2 * MONTH + 7 * DAY);
+    escrow.client.settle_security_deposit();
+
+    assert_eq!(token.balance(&tenant), 1_500);
+    assert_eq!(escrow.client.get_security_deposit().held, 0);
+    assert_eq!(escrow.client.get_settlement(), None);
+    assert_eq!(
+        escrow.client.try_settle_security_deposit(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_cancel_returns_security_deposit() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| {
+        terms.security_deposit = 2_000;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    fund_security_deposit(&escrow);
+
+    escrow.client.cancel();
+
+    assert_eq!(token.balance(&escrow.tenants.get(0).unwrap()), 1_000);
+    assert_eq!(token.balance(&escrow.tenants.get(1).unwrap()), 1_000);
+    assert_eq!(escrow.client.get_security_deposit().held, 0);
+}
+
+#[test]
+fn test_initialize_with_negative_security_deposit() {
+    let escrow = setup_escrow(1);
+
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &1_000,
+            &escrow.token,
+            &Terms {
+                security_deposit: -1,
+                ..default_terms(&escrow.env)
+            },
+        ),
+        Err(Ok(Error::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_invalid_contest_window_is_rejected() {
+    let escrow = setup_escrow(1);
+
+    assert_eq!(
+        escrow.client.try_initialize(
+            &escrow.landlord,
+            &escrow.tenants,
+            &1_000,
+            &escrow.token,
+            &Terms {
+                contest_window: 0,
+                ..default_terms(&escrow.env)
+            },
+        ),
+        Err(Ok(Error::InvalidSchedule))
+    );
+}
+
+fn deduction(escrow: &Escrow, amount: i128, reason: u8) -> Deduction {
+    Deduction {
+        amount,
+        reason: BytesN::from_array(&escrow.env, &[reason; 32]),
+    }
+}
+
+/// A two-tenant escrow whose 2 000 security deposit is in and whose single
+/// period of rent has been paid, at the moment the lease ends.
+fn ended_escrow() -> Escrow {
+    ended_escrow_with(|_, _| {})
+}
+
+/// Like [`ended_escrow`], letting `configure` adjust the terms first.
+fn ended_escrow_with(configure: impl FnOnce(&Env, &mut Terms)) -> Escrow {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        terms.split = Split::Amounts(vec![env, 750, 250]);
+        terms.security_deposit = 2_000;
+        configure(env, terms);
+    });
+    fund_security_deposit(&escrow);
+    fund_period(&escrow);
+    set_time(&escrow, MONTH);
+    escrow
+}
+
+#[test]
+fn test_contested_settlement_emits_events() {
+    let escrow = ended_escrow();
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    let deductions = vec![
+        &escrow.env,
+        deduction(&escrow, 300, 1),
+        deduction(&escrow, 100, 2),
+    ];
+
+    escrow.client.propose_deductions(&deductions);
+    escrow.client.contest_deductions(&second);
+    escrow.client.rule_on_deductions(&arbiter(&escrow), &100);
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "security_funded",
+                &first,
+                SecurityFundedEvent { amount: 1_500 },
+            ),
+            event(
+                &escrow,
+                "security_funded",
+                &second,
+                SecurityFundedEvent { amount: 500 },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &first,
+                DepositedEvent {
+                    period: 0,
+                    rent: 750,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &second,
+                DepositedEvent {
+                    period: 0,
+                    rent: 250,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "disbursed",
+                &escrow.landlord,
+                DisbursedEvent {
+                    period: 0,
+                    amount: 1_000,
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Active,
+                    to: ContractStatus::Fulfilled,
+                },
+            ),
+            event(
+                &escrow,
+                "deductions_proposed",
+                &escrow.landlord,
+                DeductionsEvent { total: 400 },
+            ),
+            event(
+                &escrow,
+                "deductions_contested",
+                &second,
+                DeductionsEvent { total: 400 },
+            ),
+            event(
+                &escrow,
+                "deductions_vote_cast",
+                &arbiter(&escrow),
+                DeductionsEvent { total: 100 },
+            ),
+            event(
+                &escrow,
+                "security_refunded",
+                &first,
+                SecurityRefundedEvent { amount: 1_425 },
+            ),
+            event(
+                &escrow,
+                "security_refunded",
+                &second,
+                SecurityRefundedEvent { amount: 475 },
+            ),
+            event(
+                &escrow,
+                "security_settled",
+                &escrow.landlord,
+                SecuritySettledEvent {
+                    deducted: 100,
+                    refunded: 1_900,
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_settled_security_deposit_emits_events() {
+    let escrow = ended_escrow();
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    let deductions = vec![&escrow.env, deduction(&escrow, 400, 1)];
+
+    escrow.client.propose_deductions(&deductions);
+    escrow.client.accept_deductions(&first);
+    escrow.client.accept_deductions(&second);
+    escrow.client.settle_security_deposit();
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "security_funded",
+                &first,
+                SecurityFundedEvent { amount: 1_500 },
+            ),
+            event(
+                &escrow,
+                "security_funded",
+                &second,
+                SecurityFundedEvent { amount: 500 },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &first,
+                DepositedEvent {
+                    period: 0,
+                    rent: 750,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &second,
+                DepositedEvent {
+                    period: 0,
+                    rent: 250,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "disbursed",
+                &escrow.landlord,
+                DisbursedEvent {
+                    period: 0,
+                    amount: 1_000,
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Active,
+                    to: ContractStatus::Fulfilled,
+                },
+            ),
+            event(
+                &escrow,
+                "deductions_proposed",
+                &escrow.landlord,
+                DeductionsEvent { total: 400 },
+            ),
+            event(
+                &escrow,
+                "security_refunded",
+                &first,
+                SecurityRefundedEvent { amount: 1_200 },
+            ),
+            event(
+                &escrow,
+                "security_refunded",
+                &second,
+                SecurityRefundedEvent { amount: 400 },
+            ),
+            event(
+                &escrow,
+                "security_settled",
+                &escrow.landlord,
+                SecuritySettledEvent {
+                    deducted: 400,
+                    refunded: 1_600,
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_landlord_proposes_itemized_deductions() {
+    let escrow = ended_escrow();
+    let deductions = vec![
+        &escrow.env,
+        deduction(&escrow, 300, 1),
+        deduction(&escrow, 100, 2),
+    ];
+
+    escrow.client.propose_deductions(&deductions);
+
+    let settlement = escrow.client.get_settlement().unwrap();
+    assert_eq!(settlement.deductions, deductions);
+    assert_eq!(settlement.total, 400);
+    assert_eq!(settlement.proposed_at, MONTH);
+    assert_eq!(settlement.respond_by, MONTH + 7 * DAY);
+    assert_eq!(settlement.status, SettlementStatus::Proposed);
+    assert_eq!(
+        escrow.client.try_propose_deductions(&deductions),
+        Err(Ok(Error::InvalidState))
+    );
+}
+
+#[test]
+fn test_deductions_only_after_lease_end() {
+    let escrow = ended_escrow();
+    let deductions = vec![&escrow.env, deduction(&escrow, 300, 1)];
+
+    set_time(&escrow, MONTH - 1);
+    assert_eq!(
+        escrow.client.try_propose_deductions(&deductions),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    set_time(&escrow, MONTH + 7 * DAY);
+    assert_eq!(
+        escrow.client.try_propose_deductions(&deductions),
+        Err(Ok(Error::Expired))
+    );
+}
+
+#[test]
+fn test_invalid_deductions_are_rejected() {
+    let escrow = ended_escrow();
+
+    assert_eq!(
+        escrow
+            .client
+            .try_propose_deductions(&vec![&escrow.env, deduction(&escrow, 0, 1)]),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        escrow.client.try_propose_deductions(&vec![
+            &escrow.env,
+            deduction(&escrow, 1_500, 1),
+            deduction(&escrow, 501, 2),
+        ]),
+        Err(Ok(Error::InsufficientFunds))
+    );
+}
+
+#[test]
+fn test_accepted_deductions_are_settled() {
+    let escrow = ended_escrow();
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    escrow
+        .client
+        .propose_deductions(&vec![&escrow.env, deduction(&escrow, 400, 1)]);
+
+    escrow.client.accept_deductions(&first);
+    assert_eq!(
+        escrow.client.try_settle_security_deposit(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+    escrow.client.accept_deductions(&second);
+    assert_eq!(
+        escrow.client.get_settlement().unwrap().status,
+        SettlementStatus::Accepted
+    );
+
+    escrow.client.settle_security_deposit();
+
+    assert_eq!(token.balance(&escrow.landlord), 1_400);
+    assert_eq!(token.balance(&first), 1_200);
+    assert_eq!(token.balance(&second), 400);
+    assert_eq!(escrow.client.get_security_deposit().held, 0);
+    assert_eq!(
+        escrow.client.get_settlement().unwrap().status,
+        SettlementStatus::Settled
+    );
+}
+
+#[test]
+fn test_uncontested_deductions_accept_when_window_lapses() {
+    let escrow = ended_escrow();
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    escrow
+        .client
+        .propose_deductions(&vec![&escrow.env, deduction(&escrow, 400, 1)]);
+    escrow
+        .client
+        .accept_deductions(&escrow.tenants.get(0).unwrap());
+
+    set_time(&escrow, MONTH + 7 * DAY);
+
+    assert_eq!(
+        escrow.client.get_settlement().unwrap().status,
+        SettlementStatus::Accepted
+    );
+    assert_eq!(
+        escrow
+            .client
+            .try_contest_deductions(&escrow.tenants.get(1).unwrap()),
+        Err(Ok(Error::InvalidState))
+    );
+    escrow.client.settle_security_deposit();
+    assert_eq!(token.balance(&escrow.landlord), 1_400);
+    assert_eq!(token.balance(&escrow.tenants.get(1).unwrap()), 400);
+}
+
+#[test]
+fn test_panel_rules_on_contested_deductions() {
+    let escrow = ended_escrow();
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    let arbiter = arbiter(&escrow);
+    escrow
+        .client
+        .propose_deductions(&vec![&escrow.env, deduction(&escrow, 400, 1)]);
+    assert_eq!(
+        escrow.client.try_rule_on_deductions(&arbiter, &100),
+        Err(Ok(Error::InvalidState))
+    );
+
+    escrow.client.contest_deductions(&second);
+
+    let settlement = escrow.client.get_settlement().unwrap();
+    assert_eq!(settlement.status, SettlementStatus::Contested);
+    assert_eq!(settlement.rule_by, Some(MONTH + 14 * DAY));
+    assert_eq!(
+        escrow.client.try_accept_deductions(&second),
+        Err(Ok(Error::InvalidState))
+    );
+    assert_eq!(
+        escrow.client.try_settle_security_deposit(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+    assert_eq!(
+        escrow.client.try_rule_on_deductions(&escrow.landlord, &400),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        escrow.client.try_rule_on_deductions(&arbiter, &401),
+        Err(Ok(Error::InvalidAmount))
+    );
+
+    escrow.client.rule_on_deductions(&arbiter, &100);
+
+    assert_eq!(token.balance(&escrow.landlord), 1_100);
+    assert_eq!(token.balance(&first), 1_425);
+    assert_eq!(token.balance(&second), 475);
+    assert_eq!(escrow.client.get_security_deposit().held, 0);
+    let settlement = escrow.client.get_settlement().unwrap();
+    assert_eq!(settlement.status, SettlementStatus::Settled);
+    assert_eq!(settlement.votes.get(arbiter), Some(100));
+}
+
+#[test]
+fn test_contested_deductions_wait_for_panel_threshold() {
+    let escrow = ended_escrow_with(|env, terms| {
+        terms.arbitration.arbiters = vec![
+            env,
+            Address::generate(env),
+            Address::generate(env),
+            Address::generate(env),
+        ];
+        terms.arbitration.threshold = 2;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let arbiters = escrow.client.get_agreement().arbitration.arbiters;
+    escrow
+        .client
+        .propose_deductions(&vec![&escrow.env, deduction(&escrow, 400, 1)]);
+    escrow
+        .client
+        .contest_deductions(&escrow.tenants.get(0).unwrap());
+
+    escrow
+        .client
+        .rule_on_deductions(&arbiters.get(0).unwrap(), &400);
+    escrow
+        .client
+        .rule_on_deductions(&arbiters.get(1).unwrap(), &200);
+    assert_eq!(escrow.client.get_security_deposit().held, 2_000);
+
+    escrow
+        .client
+        .rule_on_deductions(&arbiters.get(0).unwrap(), &200);
+
+    assert_eq!(token.balance(&escrow.landlord), 1_200);
+    assert_eq!(escrow.client.get_security_deposit().held, 0);
+}
+
+#[test]
+fn test_contested_deductions_fall_back_when_panel_does_not_rule() {
+    for (fallback, deducted) in [(Fallback::Refund, 0), (Fallback::Release, 400)] {
+        let escrow = ended_escrow_with(|_, terms| terms.arbitration.fallback = fallback);
+        let token = TokenClient::new(&escrow.env, &escrow.token);
+        escrow
+            .client
+            .propose_deductions(&vec![&escrow.env, deduction(&escrow, 400, 1)]);
+        escrow
+            .client
+            .contest_deductions(&escrow.tenants.get(1).unwrap());
+
+        set_time(&escrow, MONTH + 14 * DAY - 1);
+        assert_eq!(
+            escrow.client.try_settle_security_deposit(),
+            Err(Ok(Error::ConditionNotMet))
+        );
+
+        set_time(&escrow, MONTH + 14 * DAY);
+        escrow.client.settle_security_deposit();
+
+        assert_eq!(token.balance(&escrow.landlord), 1_000 + deducted);
+        assert_eq!(escrow.client.get_security_deposit().held, 0);
+        assert_eq!(
+            escrow.client.get_settlement().unwrap().status,
+            SettlementStatus::Settled
+        );
+    }
+}
+
+#[test]
+fn test_responding_without_proposal() {
+    let escrow = ended_escrow();
+
+    assert_eq!(
+        escrow
+            .client
+            .try_accept_deductions(&escrow.tenants.get(0).unwrap()),
+        Err(Ok(Error::ConditionNotMet))
+    );
+    assert_eq!(
+        escrow
+            .client
+            .try_contest_deductions(&Address::generate(&escrow.env)),
+        Err(Ok(Error::InvalidTenant))
+    );
+}
+
+fn evidence(escrow: &Escrow) -> BytesN<32> {
+    BytesN::from_array(&escrow.env, &[7; 32])
+}
+
+/// Has the first tenant pay part of the current period, lets `party` open a
+/// dispute over it, and then has every tenant pay what they still owe, so
+/// the period is funded while frozen.
+fn fund_disputed_period(escrow: &Escrow, party: &Address) {
+    let first = escrow.tenants.get(0).unwrap();
+    mint(escrow, &first, 1);
+    escrow.client.deposit(&first, &1);
+    escrow.client.open_dispute(party, &evidence(escrow));
+    for tenant in escrow.tenants.iter() {
+        let owed = escrow.client.get_outstanding(&tenant);
+        mint(escrow, &tenant, owed);
+        escrow.client.deposit(&tenant, &owed);
+    }
+}
+
+#[test]
+fn test_open_dispute_records_opener_and_time() {
+    let escrow = setup_initialized_escrow(2, 1_000);
+    let tenant = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &tenant, 500);
+    escrow.client.deposit(&tenant, &500);
+    set_time(&escrow, 5 * DAY);
+
+    escrow.client.open_dispute(&tenant, &evidence(&escrow));
+
+    let dispute = Dispute {
+        period: 0,
+        opened_by: tenant.clone(),
+        opened_at: 5 * DAY,
+        deadline: 19 * DAY,
+        evidence: evidence(&escrow),
+        status: DisputeStatus::Open,
+        votes: Map::new(&escrow.env),
+        resolved_at: None,
+        to_landlord: 0,
+        to_tenants: 0,
+    };
+    assert_eq!(escrow.client.get_dispute(&0), Some(dispute.clone()));
+    assert_eq!(escrow.client.get_open_dispute(), Some(dispute));
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 0,
+                    rent: 500,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "dispute_opened",
+                &tenant,
+                DisputeOpenedEvent {
+                    period: 0,
+                    evidence: evidence(&escrow),
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_open_dispute_requires_party() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+
+    assert_eq!(
+        escrow
+            .client
+            .try_open_dispute(&Address::generate(&escrow.env), &evidence(&escrow)),
+        Err(Ok(Error::Unauthorized))
+    );
+
+    escrow
+        .client
+        .open_dispute(&escrow.landlord, &evidence(&escrow));
+    assert_eq!(
+        escrow.client.try_open_dispute(&tenant, &evidence(&escrow)),
+        Err(Ok(Error::Disputed))
+    );
+}
+
+#[test]
+fn test_dispute_needs_funds_in_escrow() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    assert_eq!(
+        escrow
+            .client
+            .try_open_dispute(&escrow.landlord, &evidence(&escrow)),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    fund_period(&escrow);
+
+    assert_eq!(
+        escrow
+            .client
+            .try_open_dispute(&escrow.landlord, &evidence(&escrow)),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_dispute_freezes_automatic_disbursement() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| terms.schedule.periods = 2);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+
+    fund_disputed_period(&escrow, &escrow.tenants.get(0).unwrap());
+
+    assert_eq!(token.balance(&escrow.landlord), 0);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Funded);
+    assert_eq!(escrow.client.get_current_period(), 1);
+    assert_eq!(escrow.client.get_balance(), 1_000);
+
+    fund_period(&escrow);
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Disbursed);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Funded);
+}
+
+#[test]
+fn test_dispute_freezes_withdrawal_of_disputed_period() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.schedule.periods = 3;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    fund_period(&escrow);
+    escrow
+        .client
+        .open_dispute(&escrow.tenants.get(0).unwrap(), &evidence(&escrow));
+
+    assert_eq!(
+        escrow.client.try_withdraw(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    fund_period(&escrow);
+    escrow.client.withdraw();
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Funded);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Disbursed);
+}
+
+#[test]
+fn test_rejected_dispute_leaves_skipped_period_withdrawable() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.schedule.periods = 3;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    fund_period(&escrow);
+    escrow
+        .client
+        .open_dispute(&escrow.tenants.get(0).unwrap(), &evidence(&escrow));
+    fund_period(&escrow);
+    escrow.client.withdraw();
+
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Rejected);
+    escrow.client.withdraw();
+
+    assert_eq!(token.balance(&escrow.landlord), 2_000);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Disbursed);
+    assert_eq!(escrow.client.get_balance(), 0);
+
+    fund_period(&escrow);
+    escrow.client.withdraw();
+    assert_eq!(token.balance(&escrow.landlord), 3_000);
+}
+
+#[test]
+fn test_cancel_after_rejected_dispute_skips_periods_paid_out() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.schedule.periods = 3;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    fund_period(&escrow);
+    escrow.client.open_dispute(&tenant, &evidence(&escrow));
+    fund_period(&escrow);
+    escrow.client.withdraw();
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Rejected);
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+
+    escrow.client.cancel();
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(token.balance(&tenant), 1_400);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Cancelled);
+    assert_eq!(escrow.client.get_period(&1).status, PeriodStatus::Disbursed);
+    assert_eq!(escrow.client.get_period(&2).status, PeriodStatus::Cancelled);
+    assert_eq!(escrow.client.get_balance(), 0);
+}
+
+#[test]
+fn test_dispute_freezes_refunds_and_cancellation() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+    escrow
+        .client
+        .open_dispute(&escrow.landlord, &evidence(&escrow));
+
+    assert_eq!(escrow.client.try_cancel(), Err(Ok(Error::Disputed)));
+
+    set_time(&escrow, MONTH);
+    assert_eq!(
+        escrow.client.try_claim_refund(&tenant),
+        Err(Ok(Error::Disputed))
+    );
+    assert_eq!(escrow.client.get_balance(), 400);
+}
+
+#[test]
+fn test_arbiter_splits_disputed_rent() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        terms.split = Split::Amounts(vec![env, 600, 400]);
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    fund_disputed_period(&escrow, &escrow.landlord);
+    set_time(&escrow, 20 * DAY);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Resolved(700));
+
+    assert_eq!(token.balance(&escrow.landlord), 700);
+    assert_eq!(token.balance(&escrow.tenants.get(0).unwrap()), 180);
+    assert_eq!(token.balance(&escrow.tenants.get(1).unwrap()), 120);
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(
+        escrow.client.get_period(&0).status,
+        PeriodStatus::Arbitrated
+    );
+    assert_eq!(escrow.client.get_open_dispute(), None);
+
+    let dispute = escrow.client.get_dispute(&0).unwrap();
+    assert_eq!(dispute.status, DisputeStatus::Resolved);
+    assert_eq!(dispute.resolved_at, Some(20 * DAY));
+    assert_eq!(dispute.to_landlord, 700);
+    assert_eq!(dispute.to_tenants, 300);
+}
+
+#[test]
+fn test_refund_reopens_period_being_collected() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| terms.schedule.periods = 2);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+    escrow.client.open_dispute(&tenant, &evidence(&escrow));
+
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Refunded);
+
+    assert_eq!(token.balance(&tenant), 400);
+    let period = escrow.client.get_period(&0);
+    assert_eq!(period.status, PeriodStatus::Open);
+    assert_eq!(period.collected, 0);
+    assert_eq!(escrow.client.get_paid(&tenant), 0);
+    assert_eq!(escrow.client.get_current_period(), 0);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+    assert_eq!(escrow.client.get_balance(), 0);
+    assert_eq!(
+        escrow.client.get_dispute(&0).unwrap().status,
+        DisputeStatus::Refunded
+    );
+
+    fund_period(&escrow);
+    fund_period(&escrow);
+    assert_eq!(token.balance(&escrow.landlord), 2_000);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+}
+
+#[test]
+fn test_fallback_reopens_unpaid_period() {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |_, terms| terms.schedule.periods = 2);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 300);
+    escrow.client.deposit(&tenant, &300);
+    escrow.client.open_dispute(&tenant, &evidence(&escrow));
+    set_time(&escrow, 14 * DAY);
+
+    escrow.client.apply_fallback();
+
+    assert_eq!(token.balance(&tenant), 300);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Open);
+    assert_eq!(escrow.client.get_current_period(), 0);
+    assert_eq!(escrow.client.get_remaining(), 1_000);
+    assert_eq!(escrow.client.get_outstanding(&tenant), 500);
+
+    fund_period(&escrow);
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_current_period(), 1);
+}
+
+#[test]
+fn test_refunded_last_period_cancels_escrow() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    fund_disputed_period(&escrow, &tenant);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Active);
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &1),
+        Err(Ok(Error::InvalidState))
+    );
+
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Refunded);
+
+    assert_eq!(escrow.client.get_status(), ContractStatus::Cancelled);
+    assert_eq!(token.balance(&escrow.landlord), 0);
+    assert_eq!(token.balance(&tenant), 1_000);
+    assert_eq!(
+        escrow.client.get_period(&0).status,
+        PeriodStatus::Arbitrated
+    );
+}
+
+#[test]
+fn test_ruling_on_last_period_fulfills_escrow_when_rent_is_released() {
+    for outcome in [Outcome::Rejected, Outcome::Resolved(600)] {
+        let escrow = setup_initialized_escrow(1, 1_000);
+        let token = TokenClient::new(&escrow.env, &escrow.token);
+        fund_disputed_period(&escrow, &escrow.landlord);
+
+        escrow.client.resolve_dispute(&arbiter(&escrow), &outcome);
+
+        assert_eq!(
+            escrow.client.get_status(),
+            ContractStatus::Fulfilled,
+            "{:?}",
+            outcome
+        );
+        assert!(token.balance(&escrow.landlord) > 0);
+    }
+}
+
+#[test]
+fn test_ruling_after_lease_is_fulfilled_releases_withheld_rent() {
+    for (outcome, to_landlord) in [
+        (Outcome::Rejected, 1_000),
+        (Outcome::Resolved(600), 600),
+        (Outcome::Refunded, 0),
+    ] {
+        let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+            terms.payout = PayoutMode::Manual;
+        });
+        let token = TokenClient::new(&escrow.env, &escrow.token);
+        let tenant = escrow.tenants.get(0).unwrap();
+        fund_period(&escrow);
+        assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+        escrow.client.open_dispute(&tenant, &evidence(&escrow));
+
+        escrow.client.resolve_dispute(&arbiter(&escrow), &outcome);
+        if outcome == Outcome::Rejected {
+            escrow.client.withdraw();
+        }
+
+        assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+        assert_eq!(
+            token.balance(&escrow.landlord),
+            to_landlord,
+            "{:?}",
+            outcome
+        );
+        assert_eq!(token.balance(&tenant), 1_000 - to_landlord);
+        assert_eq!(escrow.client.get_balance(), 0);
+    }
+}
+
+#[test]
+fn test_rejected_dispute_releases_funds() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    fund_disputed_period(&escrow, &escrow.tenants.get(0).unwrap());
+
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Rejected);
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Disbursed);
+    let dispute = escrow.client.get_dispute(&0).unwrap();
+    assert_eq!(dispute.status, DisputeStatus::Rejected);
+    assert_eq!(dispute.to_landlord, 0);
+    assert_eq!(dispute.to_tenants, 0);
+}
+
+#[test]
+fn test_rejected_dispute_lets_expired_refunds_through() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+    escrow
+        .client
+        .open_dispute(&escrow.landlord, &evidence(&escrow));
+    set_time(&escrow, MONTH);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Rejected);
+    escrow.client.claim_refund(&tenant);
+
+    assert_eq!(token.balance(&tenant), 400);
+}
+
+#[test]
+fn test_withdraw_continues_past_arbitrated_period() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.payout = PayoutMode::Manual;
+        terms.schedule.periods = 3;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    fund_period(&escrow);
+    escrow
+        .client
+        .open_dispute(&escrow.landlord, &evidence(&escrow));
+    fund_period(&escrow);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiter(&escrow), &Outcome::Resolved(1_000));
+    fund_period(&escrow);
+    escrow.client.withdraw();
+
+    assert_eq!(token.balance(&escrow.landlord), 3_000);
+    assert_eq!(escrow.client.get_balance(), 0);
+}
+
+#[test]
+fn test_resolution_cannot_exceed_disputed_funds() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+    escrow.client.open_dispute(&tenant, &evidence(&escrow));
+
+    assert_eq!(
+        escrow
+            .client
+            .try_resolve_dispute(&arbiter(&escrow), &Outcome::Resolved(401)),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        escrow
+            .client
+            .try_resolve_dispute(&arbiter(&escrow), &Outcome::Resolved(-1)),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(escrow.client.get_open_dispute().unwrap().period, 0);
+}
+
+#[test]
+fn test_resolve_without_open_dispute() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+
+    assert_eq!(
+        escrow
+            .client
+            .try_resolve_dispute(&arbiter(&escrow), &Outcome::Refunded),
+        Err(Ok(Error::ConditionNotMet))
+    );
+}
+
+#[test]
+fn test_resolve_dispute_requires_arbiter_auth() {
+    let escrow = setup_initialized_escrow(1, 1_000);
+    let arbiter = arbiter(&escrow);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 400);
+    escrow.client.deposit(&tenant, &400);
+    escrow
+        .client
+        .open_dispute(&escrow.landlord, &evidence(&escrow));
+
+    escrow.client.resolve_dispute(&arbiter, &Outcome::Refunded);
+
+    assert_eq!(
+        escrow.env.auths(),
+        std::vec![(
+            arbiter.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    escrow.client.address.clone(),
+                    Symbol::new(&escrow.env, "resolve_dispute"),
+                    (arbiter.clone(), Outcome::Refunded).into_val(&escrow.env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 0,
+                    rent: 400,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "dispute_opened",
+                &escrow.landlord,
+                DisputeOpenedEvent {
+                    period: 0,
+                    evidence: evidence(&escrow),
+                },
+            ),
+            event(
+                &escrow,
+                "vote_cast",
+                &arbiter,
+                VoteCastEvent {
+                    period: 0,
+                    outcome: Outcome::Refunded,
+                },
+            ),
+            event(
+                &escrow,
+                "refunded",
+                &tenant,
+                RefundedEvent {
+                    period: 0,
+                    amount: 400,
+                },
+            ),
+            event(
+                &escrow,
+                "dispute_resolved",
+                &escrow.landlord,
+                DisputeResolvedEvent {
+                    period: 0,
+                    status: DisputeStatus::Refunded,
+                    to_landlord: 0,
+                    to_tenants: 400,
+                },
+            ),
+        ]
+    );
+}
+
+/// A two-tenant escrow with a panel of `panel` arbiters and the given
+/// threshold, and an open dispute over its partly paid first period.
+fn disputed_escrow(panel: usize, threshold: u32) -> (Escrow, std::vec::Vec<Address>) {
+    let escrow = setup_initialized_escrow_with(2, 1_000, |env, terms| {
+        let mut arbiters = Vec::new(env);
+        for _ in 0..panel {
+            arbiters.push_back(Address::generate(env));
+        }
+        terms.arbitration.arbiters = arbiters;
+        terms.arbitration.threshold = threshold;
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 500);
+    escrow.client.deposit(&tenant, &500);
+    escrow
+        .client
+        .open_dispute(&escrow.landlord, &evidence(&escrow));
+    let arbiters = escrow
+        .client
+        .get_agreement()
+        .arbitration
+        .arbiters
+        .iter()
+        .collect();
+    (escrow, arbiters)
+}
+
+#[test]
+fn test_ruling_waits_for_threshold() {
+    let (escrow, arbiters) = disputed_escrow(3, 2);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Resolved(200));
+
+    let dispute = escrow.client.get_open_dispute().unwrap();
+    assert_eq!(dispute.status, DisputeStatus::Open);
+    assert_eq!(
+        dispute.votes,
+        Map::from_array(&escrow.env, [(arbiters[0].clone(), Outcome::Resolved(200))])
+    );
+    assert_eq!(escrow.client.get_balance(), 500);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[2], &Outcome::Resolved(200));
+
+    assert_eq!(escrow.client.get_open_dispute(), None);
+    assert_eq!(token.balance(&escrow.landlord), 200);
+    assert_eq!(token.balance(&escrow.tenants.get(0).unwrap()), 300);
+    let dispute = escrow.client.get_dispute(&0).unwrap();
+    assert_eq!(dispute.status, DisputeStatus::Resolved);
+    assert_eq!(dispute.votes.len(), 2);
+}
+
+#[test]
+fn test_arbiter_can_change_vote() {
+    let (escrow, arbiters) = disputed_escrow(3, 2);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Refunded);
+    escrow
+        .client
+        .resolve_dispute(&arbiters[1], &Outcome::Rejected);
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Rejected);
+
+    let dispute = escrow.client.get_dispute(&0).unwrap();
+    assert_eq!(dispute.status, DisputeStatus::Rejected);
+    assert_eq!(
+        dispute.votes.get(arbiters[0].clone()),
+        Some(Outcome::Rejected)
+    );
+    assert_eq!(token.balance(&escrow.tenants.get(0).unwrap()), 0);
+    assert_eq!(escrow.client.get_balance(), 500);
+}
+
+#[test]
+fn test_tied_panel_does_not_rule() {
+    let (escrow, arbiters) = disputed_escrow(4, 3);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Refunded);
+    escrow
+        .client
+        .resolve_dispute(&arbiters[1], &Outcome::Refunded);
+    escrow
+        .client
+        .resolve_dispute(&arbiters[2], &Outcome::Rejected);
+    escrow
+        .client
+        .resolve_dispute(&arbiters[3], &Outcome::Rejected);
+
+    let dispute = escrow.client.get_open_dispute().unwrap();
+    assert_eq!(dispute.status, DisputeStatus::Open);
+    assert_eq!(dispute.votes.len(), 4);
+    assert_eq!(escrow.client.get_balance(), 500);
+}
+
+#[test]
+fn test_panel_without_quorum_keeps_funds_frozen() {
+    let (escrow, arbiters) = disputed_escrow(3, 3);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Refunded);
+    escrow
+        .client
+        .resolve_dispute(&arbiters[1], &Outcome::Resolved(100));
+    escrow
+        .client
+        .resolve_dispute(&arbiters[2], &Outcome::Resolved(200));
+
+    assert_eq!(
+        escrow.client.get_open_dispute().unwrap().status,
+        DisputeStatus::Open
+    );
+    assert_eq!(escrow.client.try_cancel(), Err(Ok(Error::Disputed)));
+    assert_eq!(escrow.client.get_balance(), 500);
+}
+
+#[test]
+fn test_only_panel_members_vote() {
+    let (escrow, _) = disputed_escrow(3, 2);
+
+    assert_eq!(
+        escrow
+            .client
+            .try_resolve_dispute(&escrow.landlord, &Outcome::Resolved(500)),
+        Err(Ok(Error::Unauthorized))
+    );
+    assert_eq!(
+        escrow
+            .client
+            .try_resolve_dispute(&Address::generate(&escrow.env), &Outcome::Refunded),
+        Err(Ok(Error::Unauthorized))
+    );
+}
+
+#[test]
+fn test_vote_emits_event() {
+    let (escrow, arbiters) = disputed_escrow(3, 2);
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[1], &Outcome::Resolved(300));
+
+    let tenant = escrow.tenants.get(0).unwrap();
+    assert_eq!(
+        escrow_events(&escrow),
+        vec![
+            &escrow.env,
+            event(
+                &escrow,
+                "initialized",
+                &escrow.landlord,
+                InitializedEvent {
+                    tenants: escrow.tenants.clone(),
+                    rent_amount: 1_000,
+                    token: escrow.token.clone(),
+                },
+            ),
+            event(
+                &escrow,
+                "status_changed",
+                &escrow.client.address,
+                StatusChangedEvent {
+                    from: ContractStatus::Inactive,
+                    to: ContractStatus::Active,
+                },
+            ),
+            event(
+                &escrow,
+                "deposited",
+                &tenant,
+                DepositedEvent {
+                    period: 0,
+                    rent: 500,
+                    credit: 0,
+                },
+            ),
+            event(
+                &escrow,
+                "dispute_opened",
+                &escrow.landlord,
+                DisputeOpenedEvent {
+                    period: 0,
+                    evidence: evidence(&escrow),
+                },
+            ),
+            event(
+                &escrow,
+                "vote_cast",
+                &arbiters[1],
+                VoteCastEvent {
+                    period: 0,
+                    outcome: Outcome::Resolved(300),
+                },
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_panels_are_rejected() {
+    let escrow = setup_escrow(2);
+    let env = &escrow.env;
+    let arbiter = Address::generate(env);
+    let tenant = escrow.tenants.get(1).unwrap();
+    let base = default_terms(env).arbitration;
+    for arbitration in [
+        Arbitration {
+            arbiters: Vec::new(env),
+            threshold: 0,
+            ..base.clone()
+        },
+        Arbitration {
+            arbiters: vec![env, arbiter.clone()],
+            threshold: 0,
+            ..base.clone()
+        },
+        Arbitration {
+            arbiters: vec![env, arbiter.clone()],
+            threshold: 2,
+            ..base.clone()
+        },
+        Arbitration {
+            arbiters: vec![env, arbiter.clone(), arbiter.clone()],
+            threshold: 1,
+            ..base.clone()
+        },
+        Arbitration {
+            arbiters: vec![env, arbiter.clone(), escrow.landlord.clone()],
+            threshold: 1,
+            ..base.clone()
+        },
+        Arbitration {
+            arbiters: vec![env, tenant.clone()],
+            threshold: 1,
+            ..base.clone()
+        },
+        Arbitration {
+            timeout: 0,
+            ..base.clone()
+        },
+    ] {
+        assert_eq!(
+            escrow.client.try_initialize(
+                &escrow.landlord,
+                &escrow.tenants,
+                &1_000,
+                &escrow.token,
+                &Terms {
+                    arbitration: arbitration.clone(),
+                    ..default_terms(env)
+                },
+            ),
+            Err(Ok(Error::InvalidArbitration)),
+            "{:?}",
+            arbitration
+        );
+    }
+}
+
+#[test]
+fn test_fallback_refunds_tenants_after_deadline() {
+    let (escrow, arbiters) = disputed_escrow(3, 2);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Resolved(500));
+
+    set_time(&escrow, 14 * DAY - 1);
+    assert_eq!(
+        escrow.client.try_apply_fallback(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    set_time(&escrow, 14 * DAY);
+    escrow.client.apply_fallback();
+
+    assert!(escrow.env.auths().is_empty());
+    assert_eq!(token.balance(&tenant), 500);
+    assert_eq!(token.balance(&escrow.landlord), 0);
+    assert_eq!(escrow.client.get_open_dispute(), None);
+    let dispute = escrow.client.get_dispute(&0).unwrap();
+    assert_eq!(dispute.status, DisputeStatus::Refunded);
+    assert_eq!(dispute.resolved_at, Some(14 * DAY));
+    assert_eq!(dispute.to_tenants, 500);
+}
+
+#[test]
+fn test_fallback_releases_funds_to_landlord() {
+    let escrow = setup_initialized_escrow_with(1, 1_000, |_, terms| {
+        terms.schedule.periods = 2;
+        terms.arbitration.fallback = Fallback::Release;
+        terms.arbitration.timeout = 3 * DAY;
+    });
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    set_time(&escrow, DAY);
+    fund_disputed_period(&escrow, &escrow.tenants.get(0).unwrap());
+
+    set_time(&escrow, 4 * DAY);
+    escrow.client.apply_fallback();
+
+    assert_eq!(token.balance(&escrow.landlord), 1_000);
+    assert_eq!(escrow.client.get_balance(), 0);
+    let dispute = escrow.client.get_dispute(&0).unwrap();
+    assert_eq!(dispute.deadline, 4 * DAY);
+    assert_eq!(dispute.status, DisputeStatus::Resolved);
+    assert_eq!(dispute.to_landlord, 1_000);
+}
+
+#[test]
+fn test_fallback_only_for_open_dispute() {
+    let (escrow, arbiters) = disputed_escrow(1, 1);
+
+    assert_eq!(
+        escrow.client.try_apply_fallback(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+
+    escrow
+        .client
+        .resolve_dispute(&arbiters[0], &Outcome::Rejected);
+    set_time(&escrow, 30 * DAY);
+
+    assert_eq!(
+        escrow.client.try_apply_fallback(),
+        Err(Ok(Error::ConditionNotMet))
+    );
+    assert_eq!(
+        escrow.client.get_dispute(&0).unwrap().status,
+        DisputeStatus::Rejected
+    );
+}
+
+/// One whole unit of the test token and of the quote currency, both counted
+/// in 7 decimals.
+const UNIT: i128 = 10_000_000;
+
+/// An oracle price of 0.12 quote units per token.
+const PRICE: i128 = 1_200_000;
+
+/// An escrow whose 1 500 rent is set in the quote currency and priced by a
+/// mock oracle that reports `PRICE` at the start of the lease.
+fn oracle_escrow(tenant_count: u32, max_slippage: u32) -> (Escrow, Address) {
+    let mut oracle = None;
+    let escrow = setup_initialized_escrow_with(tenant_count, 1_500 * UNIT, |env, terms| {
+        let address = create_oracle(env);
+        terms.pricing = Pricing::Oracle(PriceFeed {
+            oracle: address.clone(),
+            max_age: 10 * 60,
+            max_slippage,
+        });
+        oracle = Some(address);
+    });
+    let oracle = oracle.unwrap();
+    set_price(&escrow.env, &oracle, PRICE, 0);
+    (escrow, oracle)
+}
+
+#[test]
+fn test_oracle_priced_deposit_is_converted() {
+    let (escrow, _) = oracle_escrow(1, 0);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 12_500 * UNIT);
+
+    escrow.client.deposit(&tenant, &(12_500 * UNIT));
+
+    assert_eq!(escrow.client.get_paid(&tenant), 1_500 * UNIT);
+    assert_eq!(token.balance(&escrow.landlord), 12_500 * UNIT);
+    assert_eq!(escrow.client.get_period(&0).received, 12_500 * UNIT);
+    assert_eq!(escrow.client.get_status(), ContractStatus::Fulfilled);
+}
+
+#[test]
+fn test_oracle_excess_is_credited_in_tokens() {
+    let (escrow, _) = oracle_escrow(1, 0);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 13_000 * UNIT);
+
+    escrow.client.deposit(&tenant, &(13_000 * UNIT));
+
+    assert_eq!(token.balance(&escrow.landlord), 12_500 * UNIT);
+    assert_eq!(escrow.client.get_credit(&tenant), 500 * UNIT);
+    assert_eq!(escrow.client.get_balance(), 500 * UNIT);
+}
+
+#[test]
+fn test_oracle_price_must_be_fresh() {
+    let (escrow, oracle) = oracle_escrow(1, 0);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 100 * UNIT);
+
+    set_time(&escrow, 10 * 60 + 1);
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &(100 * UNIT)),
+        Err(Ok(Error::StalePrice))
+    );
+
+    set_price(&escrow.env, &oracle, PRICE, 10 * 60 + 1);
+    escrow.client.deposit(&tenant, &(100 * UNIT));
+    assert_eq!(escrow.client.get_paid(&tenant), 12 * UNIT);
+}
+
+#[test]
+fn test_oracle_without_price_rejects_deposits() {
+    let escrow = setup_initialized_escrow_with(1, 1_500 * UNIT, |env, terms| {
+        terms.pricing = Pricing::Oracle(PriceFeed {
+            oracle: create_oracle(env),
+            max_age: 10 * 60,
+            max_slippage: 0,
+        });
+    });
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 100 * UNIT);
+
+    assert_eq!(
+        escrow.client.try_deposit(&tenant, &(100 * UNIT)),
+        Err(Ok(Error::StalePrice))
+    );
+}
+
+#[test]
+fn test_oracle_slippage_within_bound_settles_share() {
+    let (escrow, oracle) = oracle_escrow(2, 50);
+    let first = escrow.tenants.get(0).unwrap();
+    let second = escrow.tenants.get(1).unwrap();
+    mint(&escrow, &first, 6_250 * UNIT);
+    mint(&escrow, &second, 6_250 * UNIT);
+
+    // 0.33% below the price the deposit was sized for.
+    set_price(&escrow.env, &oracle, 1_196_000, 0);
+    escrow.client.deposit(&first, &(6_250 * UNIT));
+    assert_eq!(escrow.client.get_paid(&first), 750 * UNIT);
+
+    // 0.83% below it, beyond the 0.5% bound.
+    set_price(&escrow.env, &oracle, 1_190_000, 0);
+    escrow.client.deposit(&second, &(6_250 * UNIT));
+    assert_eq!(escrow.client.get_paid(&second), 74_375 * UNIT / 100);
+    assert_eq!(escrow.client.get_outstanding(&second), 625 * UNIT / 100);
+    assert_eq!(escrow.client.get_period(&0).status, PeriodStatus::Open);
+}
+
+#[test]
+fn test_oracle_refunds_return_deposited_tokens() {
+    let (escrow, oracle) = oracle_escrow(2, 0);
+    let token = TokenClient::new(&escrow.env, &escrow.token);
+    let tenant = escrow.tenants.get(0).unwrap();
+    mint(&escrow, &tenant, 5_000 * UNIT);
+    escrow.client.deposit(&tenant, &(5_000 * UNIT));
+
+    set_time(&escrow, MONTH);
+    set_price(&escrow.env, &oracle, 2 * PRICE, MONTH);
+    escrow.client.claim_refund(&tenant);
+
+    assert_eq!(token.balance(&tenant), 5_000 * UNIT);
+    assert_eq!(escrow.client.get_balance(), 0);
+}
+
+#[test]
+fn test_invalid_price_feeds_are_rejected() {
+    let escrow = setup_escrow(1);
+    let env = &escrow.env;
+    let oracle = create_oracle(env);
+    for feed in [
+        PriceFeed {
+            oracle: oracle.clone(),
+            max_age: 0,
+            max_slippage: 0,
+        },
+        PriceFeed {
+            oracle: oracle.clone(),
+            max_age: 60,
+            max_slippage: 10_000,
+        },
+    ] {
+        assert_eq!(
+            escrow.client.try_initialize(
+                &escrow.landlord,
+                &escrow.tenants,
+                &1_000,
+                &escrow.token,
+                &Terms {
+                    pricing: Pricing::Oracle(feed.clone()),
+                    ..default_terms(env)
+                },
+            ),
+            Err(Ok(Error::InvalidAmount)),
+            "{:?}",
+            feed
+        );
+    }
+
+    let feed = PriceFeed {
+        oracle,
+        max_age: 60,
+        max_slippage: 9_999,
+    };
+    escrow.client.initialize(
+        &escrow.landlord,
+        &escrow.tenants,
+        &1_000,
+        &escrow.token,
+        &Terms {
+            pricing: Pricing::Oracle(feed.clone()),
+            ..default_terms(env)
+        },
+    );
+    assert_eq!(escrow.client.get_agreement().pricing, Pricing::Oracle(feed));
+}
@@ -1,7 +1,16 @@
 #![cfg(test)]
 
-use soroban_sdk::{Env, Address, testutils::Address as _};
-use crate::{RentContract, RentContractClient};
+use crate::types::DAY;
+use crate::{
+    Arbitration, Fallback, LateFee, LateFeePolicy, Oracle, PayoutMode, PriceData, Pricing,
+    RentContract, RentContractClient, Schedule, Split, Terms, EVENT_VERSION,
+};
+use soroban_sdk::{
+    contract, contractimpl, contracttype,
+    testutils::{Address as _, Events, Ledger, LedgerInfo},
+    token::{StellarAssetClient, TokenInterface},
+    Address, Env, IntoVal, String, Symbol, Val, Vec,
+};
 
 pub fn setup_test_env() -> (Env, Address, RentContractClient<'static>) {
     let env = Env::default();
@@ -14,3 +23,325 @@ pub fn setup_test_env() -> (Env, Address, RentContractClient<'static>) {
 
     (env, user, client)
 }
+
+pub struct Escrow {
+    pub env: Env,
+    pub client: RentContractClient<'static>,
+    pub landlord: Address,
+    pub tenants: Vec<Address>,
+    pub token: Address,
+}
+
+/// Registers a Stellar Asset Contract to serve as the rent token. Like the
+/// native XLM contract, it counts amounts in 7 decimals.
+pub fn create_token(env: &Env) -> Address {
+    env.register_stellar_asset_contract_v2(Address::generate(env))
+        .address()
+}
+
+/// Registers a [`TestToken`] with 6 decimals, as USDC has on most chains.
+pub fn create_six_decimal_token(env: &Env) -> Address {
+    let token = env.register_contract(None, TestToken);
+    TestTokenClient::new(env, &token).set_decimals(&6);
+    token
+}
+
+/// Registers the contract and a token, without initializing the escrow.
+pub fn setup_escrow(tenant_count: u32) -> Escrow {
+    setup_escrow_with_token(tenant_count, create_token)
+}
+
+/// Registers the contract and the token made by `create_token`, without
+/// initializing the escrow.
+pub fn setup_escrow_with_token(
+    tenant_count: u32,
+    create_token: impl FnOnce(&Env) -> Address,
+) -> Escrow {
+    let (env, landlord, client) = setup_test_env();
+    let token = create_token(&env);
+
+    let mut tenants = Vec::new(&env);
+    for _ in 0..tenant_count {
+        tenants.push_back(Address::generate(&env));
+    }
+
+    Escrow {
+        env,
+        client,
+        landlord,
+        tenants,
+        token,
+    }
+}
+
+/// Length of a rent period in the test schedules.
+pub const MONTH: u64 = 30 * 24 * 60 * 60;
+
+/// Terms for a single period starting now, split evenly and paid out as soon
+/// as the rent is collected.
+pub fn default_terms(env: &Env) -> Terms {
+    Terms {
+        payout: PayoutMode::Automatic,
+        split: Split::Equal,
+        schedule: Schedule {
+            start: env.ledger().timestamp(),
+            interval: MONTH,
+            periods: 1,
+        },
+        late_fee: LateFee {
+            grace: 0,
+            policy: LateFeePolicy::None,
+        },
+        arbitration: Arbitration {
+            arbiters: Vec::from_array(env, [Address::generate(env)]),
+            threshold: 1,
+            timeout: 14 * DAY,
+            fallback: Fallback::Refund,
+        },
+        security_deposit: 0,
+        contest_window: 7 * DAY,
+        pricing: Pricing::Token,
+    }
+}
+
+/// Registers and initializes an escrow for `rent_amount` with the default
+/// terms.
+pub fn setup_initialized_escrow(tenant_count: u32, rent_amount: i128) -> Escrow {
+    setup_initialized_escrow_with(tenant_count, rent_amount, |_, _| {})
+}
+
+/// Registers and initializes an escrow for `rent_amount`, letting `configure`
+/// adjust the default terms first.
+pub fn setup_initialized_escrow_with(
+    tenant_count: u32,
+    rent_amount: i128,
+    configure: impl FnOnce(&Env, &mut Terms),
+) -> Escrow {
+    let escrow = setup_escrow(tenant_count);
+    let mut terms = default_terms(&escrow.env);
+    configure(&escrow.env, &mut terms);
+    escrow.client.initialize(
+        &escrow.landlord,
+        &escrow.tenants,
+        &rent_amount,
+        &escrow.token,
+        &terms,
+    );
+    escrow
+}
+
+/// Moves the ledger clock to `timestamp`.
+pub fn set_time(escrow: &Escrow, timestamp: u64) {
+    escrow.env.ledger().set(LedgerInfo {
+        timestamp,
+        ..escrow.env.ledger().get()
+    });
+}
+
+/// Mints `amount` of the escrow token to `to`. Works for both Stellar Asset
+/// Contracts and [`TestToken`].
+pub fn mint(escrow: &Escrow, to: &Address, amount: i128) {
+    StellarAssetClient::new(&escrow.env, &escrow.token).mint(to, &amount);
+}
+
+/// Mints every tenant their share and deposits it, funding the current
+/// period.
+pub fn fund_period(escrow: &Escrow) {
+    let agreement = escrow.client.get_agreement();
+    for (tenant, share) in agreement.tenants.iter().zip(agreement.shares.iter()) {
+        mint(escrow, &tenant, share);
+        escrow.client.deposit(&tenant, &share);
+    }
+}
+
+/// Mints every tenant their part of the security deposit and puts it up.
+pub fn fund_security_deposit(escrow: &Escrow) {
+    let agreement = escrow.client.get_agreement();
+    for (tenant, share) in agreement
+        .tenants
+        .iter()
+        .zip(agreement.security_shares.iter())
+    {
+        mint(escrow, &tenant, share);
+        escrow.client.fund_security_deposit(&tenant);
+    }
+}
+
+/// Returns the first arbiter on the escrow's panel.
+pub fn arbiter(escrow: &Escrow) -> Address {
+    escrow
+        .client
+        .get_agreement()
+        .arbitration
+        .arbiters
+        .get(0)
+        .unwrap()
+}
+
+/// The events published by the escrow itself, leaving out those of the
+/// token contract.
+pub fn escrow_events(escrow: &Escrow) -> Vec<(Address, Vec<Val>, Val)> {
+    let mut events = Vec::new(&escrow.env);
+    for event in escrow.env.events().all().iter() {
+        if event.0 == escrow.client.address {
+            events.push_back(event);
+        }
+    }
+    events
+}
+
+/// The event the escrow publishes as `name` about `subject`, in the current
+/// schema version.
+pub fn event(
+    escrow: &Escrow,
+    name: &str,
+    subject: &Address,
+    data: impl IntoVal<Env, Val>,
+) -> (Address, Vec<Val>, Val) {
+    let env = &escrow.env;
+    (
+        escrow.client.address.clone(),
+        (Symbol::new(env, name), EVENT_VERSION, subject.clone()).into_val(env),
+        data.into_val(env),
+    )
+}
+
+#[contracttype]
+enum TokenKey {
+    Decimals,
+    Balance(Address),
+    Allowance(Address, Address),
+}
+
+/// A minimal token with configurable decimals. Stellar Asset Contracts always
+/// report 7, so this stands in for tokens that use another precision.
+#[contract]
+pub struct TestToken;
+
+#[contractimpl]
+impl TestToken {
+    pub fn set_decimals(env: Env, decimals: u32) {
+        env.storage().instance().set(&TokenKey::Decimals, &decimals);
+    }
+
+    pub fn mint(env: Env, to: Address, amount: i128) {
+        let balance = Self::balance(env.clone(), to.clone());
+        set_balance(&env, &to, balance + amount);
+    }
+}
+
+fn set_balance(env: &Env, id: &Address, amount: i128) {
+    if amount < 0 {
+        panic!("insufficient balance");
+    }
+    let key = TokenKey::Balance(id.clone());
+    env.storage().persistent().set(&key, &amount);
+}
+
+#[contractimpl]
+impl TokenInterface for TestToken {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        let key = TokenKey::Allowance(from, spender);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, _expiration_ledger: u32) {
+        from.require_auth();
+        let key = TokenKey::Allowance(from, spender);
+        env.storage().persistent().set(&key, &amount);
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        let key = TokenKey::Balance(id);
+        env.storage().persistent().get(&key).unwrap_or(0)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        Self::burn_unchecked(&env, &from, amount);
+        Self::mint(env, to, amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::burn_unchecked(&env, &from, amount);
+        Self::mint(env, to, amount);
+    }
+
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        Self::burn_unchecked(&env, &from, amount);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::burn_unchecked(&env, &from, amount);
+    }
+
+    fn decimals(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&TokenKey::Decimals)
+            .unwrap_or(7)
+    }
+
+    fn name(env: Env) -> String {
+        String::from_str(&env, "Test Token")
+    }
+
+    fn symbol(env: Env) -> String {
+        String::from_str(&env, "TEST")
+    }
+}
+
+impl TestToken {
+    fn burn_unchecked(env: &Env, from: &Address, amount: i128) {
+        let balance = Self::balance(env.clone(), from.clone());
+        set_balance(env, from, balance - amount);
+    }
+
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let allowance = Self::allowance(env.clone(), from.clone(), spender.clone());
+        if allowance < amount {
+            panic!("insufficient allowance");
+        }
+        let key = TokenKey::Allowance(from.clone(), spender.clone());
+        env.storage().persistent().set(&key, &(allowance - amount));
+    }
+}
+
+/// An oracle whose price is set by the test.
+#[contract]
+pub struct MockOracle;
+
+#[contracttype]
+enum OracleKey {
+    Price,
+}
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set_price(env: Env, price: i128, timestamp: u64) {
+        let data = PriceData { price, timestamp };
+        env.storage().instance().set(&OracleKey::Price, &data);
+    }
+}
+
+#[contractimpl]
+impl Oracle for MockOracle {
+    fn lastprice(env: Env, _asset: Address) -> Option<PriceData> {
+        env.storage().instance().get(&OracleKey::Price)
+    }
+}
+
+/// Registers a [`MockOracle`] with no price yet.
+pub fn create_oracle(env: &Env) -> Address {
+    env.register_contract(None, MockOracle)
+}
+
+/// Makes the oracle report `price` as recorded at `timestamp`.
+pub fn set_price(env: &Env, oracle: &Address, price: i128, timestamp: u64) {
+    MockOracleClient::new(env, oracle).set_price(&price, &timestamp);
+}
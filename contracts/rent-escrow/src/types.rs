@@ -0,0 +1,657 @@
+use soroban_sdk::{contracttype, Address, BytesN, Env, Map, Vec};
+
+use crate::error::Error;
+
+/// Basis points in a whole, i.e. 100%.
+pub const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Seconds in a day, the unit daily late fees accrue in.
+pub const DAY: u64 = 24 * 60 * 60;
+
+/// Most ledger entries `get_history` reads for one page of periods. This
+/// leaves room under the network's limit of 40 entries per transaction for
+/// the contract's own instance and code.
+pub const MAX_HISTORY_READS: u32 = 36;
+
+/// Ledger entries `get_history` reads for a period: its record, and what
+/// each tenant paid, their late fee and when they paid.
+const HISTORY_READS_PER_TENANT: u32 = 3;
+
+/// Most tenants an agreement can have, so that `get_history` can always
+/// return at least one period within `MAX_HISTORY_READS`.
+pub const MAX_TENANTS: u32 = (MAX_HISTORY_READS - 1) / HISTORY_READS_PER_TENANT;
+
+/// Lifecycle of an escrow. The discriminants match `ContractStatus` in the
+/// web app.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum ContractStatus {
+    /// Not initialized yet.
+    Inactive = 0,
+    /// Collecting rent.
+    Active = 1,
+    /// The full rent has been collected.
+    Fulfilled = 2,
+    /// The rent was not collected in time.
+    Expired = 3,
+    /// The agreement was called off, or the arbiters refunded the rent of
+    /// its last period.
+    Cancelled = 4,
+}
+
+impl ContractStatus {
+    /// Returns whether an escrow in this status may move to `next`. Only an
+    /// active escrow can change status; the other outcomes are final.
+    pub fn can_transition_to(self, next: ContractStatus) -> bool {
+        matches!(
+            (self, next),
+            (ContractStatus::Inactive, ContractStatus::Active)
+                | (ContractStatus::Active, ContractStatus::Fulfilled)
+                | (ContractStatus::Active, ContractStatus::Expired)
+                | (ContractStatus::Active, ContractStatus::Cancelled)
+        )
+    }
+}
+
+/// How collected rent reaches the landlord.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PayoutMode {
+    /// Rent is paid out by the deposit that completes it.
+    Automatic,
+    /// Rent is held until the landlord calls `withdraw`.
+    Manual,
+}
+
+/// How the rent is divided between the tenants, in tenant order.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Split {
+    /// Every tenant owes the same amount.
+    Equal,
+    /// Each tenant owes a fraction of the rent in basis points; the
+    /// fractions must add up to 10 000.
+    BasisPoints(Vec<u32>),
+    /// Each tenant owes a fixed amount; the amounts must add up to the rent.
+    Amounts(Vec<i128>),
+}
+
+impl Split {
+    /// Turns the split into one absolute share per tenant. Rounding dust is
+    /// owed by the first tenant, so the shares always add up to
+    /// `rent_amount` exactly.
+    pub fn resolve(&self, env: &Env, tenants: u32, rent_amount: i128) -> Result<Vec<i128>, Error> {
+        let mut shares = Vec::new(env);
+        match self {
+            Split::Equal => {
+                for _ in 0..tenants {
+                    shares.push_back(rent_amount / tenants as i128);
+                }
+            }
+            Split::BasisPoints(points) => {
+                if points.len() != tenants {
+                    return Err(Error::InvalidSplit);
+                }
+                let mut total = 0;
+                for bps in points.iter() {
+                    if bps == 0 {
+                        return Err(Error::InvalidSplit);
+                    }
+                    total += bps as i128;
+                    let share = rent_amount
+                        .checked_mul(bps as i128)
+                        .ok_or(Error::Overflow)?
+                        / BPS_DENOMINATOR;
+                    shares.push_back(share);
+                }
+                if total != BPS_DENOMINATOR {
+                    return Err(Error::InvalidSplit);
+                }
+            }
+            Split::Amounts(amounts) => {
+                if amounts.len() != tenants {
+                    return Err(Error::InvalidSplit);
+                }
+                let mut total: i128 = 0;
+                for amount in amounts.iter() {
+                    if amount <= 0 {
+                        return Err(Error::InvalidSplit);
+                    }
+                    total = total.checked_add(amount).ok_or(Error::Overflow)?;
+                }
+                if total != rent_amount {
+                    return Err(Error::InvalidSplit);
+                }
+                return Ok(amounts.clone());
+            }
+        }
+
+        let allotted: i128 = shares.iter().sum();
+        let first = shares.get(0).ok_or(Error::InvalidTenant)?;
+        shares.set(0, first + rent_amount - allotted);
+        if shares.iter().any(|share| share <= 0) {
+            return Err(Error::InvalidSplit);
+        }
+        Ok(shares)
+    }
+}
+
+/// When rent falls due. The lease runs for `periods` consecutive periods of
+/// `interval` seconds starting at `start`, and each period's rent is due at
+/// its beginning.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+    pub start: u64,
+    pub interval: u64,
+    pub periods: u32,
+}
+
+impl Schedule {
+    /// Returns the timestamp at which rent for `period` falls due.
+    pub fn due_date(&self, period: u32) -> u64 {
+        self.start + self.interval * period as u64
+    }
+
+    /// Returns the timestamp by which `period` must be fully funded, which is
+    /// when the following period falls due.
+    pub fn deadline(&self, period: u32) -> u64 {
+        self.due_date(period + 1)
+    }
+
+    /// Returns the timestamp at which the lease ends.
+    pub fn end(&self) -> u64 {
+        self.due_date(self.periods)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.interval == 0 || self.periods == 0 {
+            return Err(Error::InvalidSchedule);
+        }
+        self.interval
+            .checked_mul(self.periods as u64)
+            .and_then(|length| self.start.checked_add(length))
+            .ok_or(Error::InvalidSchedule)?;
+        Ok(())
+    }
+}
+
+/// Divides `total` between the tenants in proportion to their `shares` of
+/// `whole`. Rounding dust goes to the first tenant, as with the rent.
+pub(crate) fn prorate(
+    env: &Env,
+    shares: &Vec<i128>,
+    whole: i128,
+    total: i128,
+) -> Result<Vec<i128>, Error> {
+    let mut portions = Vec::new(env);
+    for share in shares.iter() {
+        let portion = total.checked_mul(share).ok_or(Error::Overflow)? / whole;
+        portions.push_back(portion);
+    }
+    let allotted: i128 = portions.iter().sum();
+    let first = portions.get(0).ok_or(Error::InvalidTenant)?;
+    portions.set(0, first + total - allotted);
+    Ok(portions)
+}
+
+/// A daily late fee: `rate` is charged for every started day a tenant is
+/// late, up to `cap` in total.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DailyFee {
+    pub rate: i128,
+    pub cap: i128,
+}
+
+/// How the fee for paying late is worked out.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LateFeePolicy {
+    /// Paying late costs nothing extra.
+    None,
+    /// A fixed amount, however late the payment is.
+    Flat(i128),
+    /// A share of the tenant's part of the rent, in basis points.
+    Percentage(u32),
+    /// An amount per day late, up to a cap.
+    Daily(DailyFee),
+}
+
+/// Late fee terms. A tenant who has not paid their share in full by
+/// `grace` seconds after the due date is charged according to `policy`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LateFee {
+    pub grace: u64,
+    pub policy: LateFeePolicy,
+}
+
+impl LateFee {
+    /// Returns the fee owed at `now` by a tenant whose `share` of the rent
+    /// was due at `due_date`.
+    pub fn charge(&self, share: i128, due_date: u64, now: u64) -> Result<i128, Error> {
+        let late_from = due_date.saturating_add(self.grace);
+        if now <= late_from {
+            return Ok(0);
+        }
+        match &self.policy {
+            LateFeePolicy::None => Ok(0),
+            LateFeePolicy::Flat(amount) => Ok(*amount),
+            LateFeePolicy::Percentage(bps) => share
+                .checked_mul(*bps as i128)
+                .map(|fee| fee / BPS_DENOMINATOR)
+                .ok_or(Error::Overflow),
+            LateFeePolicy::Daily(daily) => {
+                let days = (now - late_from).div_ceil(DAY) as i128;
+                Ok(daily.rate.saturating_mul(days).min(daily.cap))
+            }
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        let valid = match &self.policy {
+            LateFeePolicy::None => true,
+            LateFeePolicy::Flat(amount) => *amount >= 0,
+            LateFeePolicy::Percentage(bps) => *bps as i128 <= BPS_DENOMINATOR,
+            LateFeePolicy::Daily(daily) => daily.rate >= 0 && daily.cap >= 0,
+        };
+        if !valid {
+            return Err(Error::InvalidAmount);
+        }
+        Ok(())
+    }
+}
+
+/// Part of a tenant's share that falls due `offset` seconds after the
+/// period's due date, in basis points of the share.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Installment {
+    pub offset: u64,
+    pub bps: u32,
+}
+
+/// Checks that `plan` lays out the whole share in installments that fall due
+/// in order within a period of `interval` seconds. An empty plan is valid and
+/// leaves the whole share due at the period's due date.
+pub(crate) fn validate_installments(plan: &Vec<Installment>, interval: u64) -> Result<(), Error> {
+    let mut total = 0;
+    let mut previous = None;
+    for installment in plan.iter() {
+        if installment.bps == 0
+            || installment.offset >= interval
+            || previous.is_some_and(|offset| installment.offset <= offset)
+        {
+            return Err(Error::InvalidSchedule);
+        }
+        previous = Some(installment.offset);
+        total += installment.bps as i128;
+    }
+    if !plan.is_empty() && total != BPS_DENOMINATOR {
+        return Err(Error::InvalidSchedule);
+    }
+    Ok(())
+}
+
+/// Returns how much of `share` is due by each installment of `plan`, as
+/// running totals. The last installment takes whatever rounding leaves.
+pub(crate) fn installment_totals(env: &Env, plan: &Vec<Installment>, share: i128) -> Vec<i128> {
+    let mut totals = Vec::new(env);
+    let mut bps = 0;
+    for (i, installment) in plan.iter().enumerate() {
+        bps += installment.bps as i128;
+        if i as u32 + 1 == plan.len() {
+            totals.push_back(share);
+        } else {
+            totals.push_back(share * bps / BPS_DENOMINATOR);
+        }
+    }
+    totals
+}
+
+/// What happens to disputed funds when the arbiters do not rule in time.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fallback {
+    /// Refund the disputed funds to the tenants.
+    Refund,
+    /// Release the disputed funds to the landlord.
+    Release,
+}
+
+/// The panel that resolves disputes. A ruling is carried out once
+/// `threshold` of the `arbiters` vote for the same outcome. If no ruling
+/// is reached within `timeout` seconds of a dispute being opened, anyone can
+/// apply the `fallback`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Arbitration {
+    pub arbiters: Vec<Address>,
+    pub threshold: u32,
+    pub timeout: u64,
+    pub fallback: Fallback,
+}
+
+impl Arbitration {
+    /// Checks that the panel is non-empty, has no duplicates or parties to
+    /// the agreement on it, that its threshold can be met and that it gets
+    /// some time to rule.
+    pub(crate) fn validate(&self, landlord: &Address, tenants: &Vec<Address>) -> Result<(), Error> {
+        if self.threshold == 0 || self.threshold > self.arbiters.len() || self.timeout == 0 {
+            return Err(Error::InvalidArbitration);
+        }
+        for (i, arbiter) in self.arbiters.iter().enumerate() {
+            if arbiter == *landlord
+                || tenants.contains(&arbiter)
+                || self.arbiters.first_index_of(&arbiter) != Some(i as u32)
+            {
+                return Err(Error::InvalidArbitration);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prices the rent in a quote currency instead of the token. Each deposit is
+/// converted at the latest price of the `oracle`, which must be at most
+/// `max_age` seconds old. A deposit that falls short of settling a share by
+/// no more than `max_slippage` basis points of it settles it anyway, so a
+/// price moving between signing and execution does not leave dust unpaid.
+/// The bound must stay below 10 000, which would waive the whole share.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceFeed {
+    pub oracle: Address,
+    pub max_age: u64,
+    pub max_slippage: u32,
+}
+
+impl PriceFeed {
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.max_age == 0 || self.max_slippage as i128 >= BPS_DENOMINATOR {
+            return Err(Error::InvalidAmount);
+        }
+        Ok(())
+    }
+
+    /// Returns whether `shortfall` left on `share` is small enough to let
+    /// the share count as settled.
+    pub(crate) fn absorbs(&self, shortfall: i128, share: i128) -> bool {
+        shortfall.saturating_mul(BPS_DENOMINATOR) <= share.saturating_mul(self.max_slippage as i128)
+    }
+}
+
+/// What currency the rent is set in.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Pricing {
+    /// The rent is set in the token.
+    Token,
+    /// The rent is set in a quote currency and converted at an oracle price.
+    Oracle(PriceFeed),
+}
+
+impl Pricing {
+    /// Returns the price feed, if the rent is priced by an oracle.
+    pub(crate) fn feed(&self) -> Option<&PriceFeed> {
+        match self {
+            Pricing::Token => None,
+            Pricing::Oracle(feed) => Some(feed),
+        }
+    }
+}
+
+/// The configurable terms of an agreement.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Terms {
+    /// Whether rent is sent to the landlord as soon as it is collected or
+    /// held until the landlord withdraws it.
+    pub payout: PayoutMode,
+    /// How much of the rent each tenant owes.
+    pub split: Split,
+    /// When each period's rent falls due.
+    pub schedule: Schedule,
+    /// What tenants are charged for paying late.
+    pub late_fee: LateFee,
+    /// Who resolves disputes between the landlord and the tenants.
+    pub arbitration: Arbitration,
+    /// Security deposit the tenants put up before paying rent, held until
+    /// the lease ends. Zero for none.
+    pub security_deposit: i128,
+    /// Seconds tenants have to answer the landlord's proposed deductions
+    /// from the security deposit, and the landlord has after the lease ends
+    /// to propose them. Cannot be zero.
+    pub contest_window: u64,
+    /// What currency the rent and late fees are set in. The security
+    /// deposit is always set in the token.
+    pub pricing: Pricing,
+}
+
+/// Where a period's rent stands.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PeriodStatus {
+    /// Still collecting rent.
+    Open,
+    /// The full rent is in escrow, waiting to be paid out.
+    Funded,
+    /// The rent has been paid out to the landlord.
+    Disbursed,
+    /// The deadline passed before the full rent was collected, and tenants
+    /// can claim back what they deposited.
+    Expired,
+    /// The escrow was cancelled before the rent was paid out, and the
+    /// deposits went back to the tenants.
+    Cancelled,
+    /// A dispute over the period ended with the arbiters splitting its funds
+    /// between the landlord and the tenants.
+    Arbitrated,
+}
+
+/// Rent collection for a single period of the lease.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Period {
+    pub due_date: u64,
+    pub collected: i128,
+    /// Late fees collected on top of the rent, paid out with it.
+    pub late_fees: i128,
+    /// Tokens received for the period, late fees included. The same as
+    /// `collected + late_fees` unless the rent is priced by an oracle.
+    pub received: i128,
+    pub status: PeriodStatus,
+    /// When the rent was paid out to the landlord, if it was.
+    pub paid_out_at: Option<u64>,
+}
+
+/// What one tenant paid towards a period.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Payment {
+    pub tenant: Address,
+    pub paid: i128,
+    pub late_fee: i128,
+    /// When the tenant last paid towards the period, if they did.
+    pub paid_at: Option<u64>,
+}
+
+/// A period of the lease as reported by `get_history`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PeriodRecord {
+    pub period: u32,
+    pub due_date: u64,
+    /// One entry per tenant, in tenant order.
+    pub payments: Vec<Payment>,
+    pub late_fees: i128,
+    pub status: PeriodStatus,
+    pub paid_out_at: Option<u64>,
+}
+
+/// The rent agreement an escrow instance is bound to.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Agreement {
+    pub landlord: Address,
+    pub tenants: Vec<Address>,
+    /// The part of the rent owed by each tenant, in tenant order.
+    pub shares: Vec<i128>,
+    pub rent_amount: i128,
+    pub token: Address,
+    /// Decimal places of `token`. Every amount in the agreement is counted in
+    /// the token's smallest unit.
+    pub decimals: u32,
+    pub payout: PayoutMode,
+    pub schedule: Schedule,
+    pub late_fee: LateFee,
+    pub arbitration: Arbitration,
+    pub security_deposit: i128,
+    pub contest_window: u64,
+    /// The part of the security deposit owed by each tenant, in proportion
+    /// to their share of the rent.
+    pub security_shares: Vec<i128>,
+    pub pricing: Pricing,
+}
+
+impl Agreement {
+    /// Returns the part of the rent owed by `tenant`, or `None` if the
+    /// address is not a party to the agreement.
+    pub fn share_of(&self, tenant: &Address) -> Option<i128> {
+        let index = self.tenants.first_index_of(tenant)?;
+        self.shares.get(index)
+    }
+
+    /// Returns the part of the security deposit owed by `tenant`, or `None`
+    /// if the address is not a party to the agreement.
+    pub fn security_share_of(&self, tenant: &Address) -> Option<i128> {
+        let index = self.tenants.first_index_of(tenant)?;
+        self.security_shares.get(index)
+    }
+
+    /// Returns the most periods `get_history` returns per call, which shrinks
+    /// as tenants are added so a page stays within `MAX_HISTORY_READS`.
+    pub fn history_page(&self) -> u32 {
+        MAX_HISTORY_READS / (1 + HISTORY_READS_PER_TENANT * self.tenants.len())
+    }
+}
+
+/// The security deposit as a whole.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecurityDeposit {
+    /// What the tenants owe in total.
+    pub amount: i128,
+    /// What is currently held in escrow.
+    pub held: i128,
+    /// When the lease ends and the deposit stops being locked.
+    pub locked_until: u64,
+}
+
+/// An amount the landlord wants to keep from the security deposit, with a
+/// hash of the document explaining why.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Deduction {
+    pub amount: i128,
+    pub reason: BytesN<32>,
+}
+
+/// Where the landlord's proposed deductions stand.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SettlementStatus {
+    /// Waiting for the tenants to accept or contest.
+    Proposed,
+    /// A tenant contested the deductions, and the arbiter panel rules on
+    /// them.
+    Contested,
+    /// Every tenant accepted, or the window lapsed without a contest.
+    Accepted,
+    /// The deductions went to the landlord and the rest to the tenants.
+    Settled,
+}
+
+/// The landlord's proposal for settling the security deposit at move-out.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Settlement {
+    pub deductions: Vec<Deduction>,
+    /// The sum of the deductions.
+    pub total: i128,
+    pub proposed_at: u64,
+    /// When the deductions are accepted unless a tenant contests them.
+    pub respond_by: u64,
+    /// Tenants who have accepted the deductions.
+    pub accepted: Vec<Address>,
+    pub status: SettlementStatus,
+    /// Once contested, when anyone can apply the arbiter panel's fallback if
+    /// it has not ruled.
+    pub rule_by: Option<u64>,
+    /// The deductions each arbiter currently votes to allow.
+    pub votes: Map<Address, i128>,
+}
+
+/// Where a dispute stands. Apart from `Open`, the values match the
+/// `dispute_status` enum in the web app.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DisputeStatus {
+    /// Waiting for a resolution; the disputed funds are frozen.
+    Open,
+    /// The arbiters split the funds between the landlord and the tenants.
+    Resolved,
+    /// The arbiters dismissed the dispute and the funds were released as if
+    /// it had never been opened.
+    Rejected,
+    /// The arbiters returned the funds to the tenants.
+    Refunded,
+}
+
+/// How the arbiters rule on a dispute.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// Pay this much of the disputed funds to the landlord and refund the
+    /// rest to the tenants, in proportion to what each deposited.
+    Resolved(i128),
+    /// Dismiss the dispute and release the funds.
+    Rejected,
+    /// Refund all the disputed funds to the tenants.
+    Refunded,
+}
+
+impl Outcome {
+    /// Returns the dispute status this outcome is recorded as.
+    pub fn status(&self) -> DisputeStatus {
+        match self {
+            Outcome::Resolved(_) => DisputeStatus::Resolved,
+            Outcome::Rejected => DisputeStatus::Rejected,
+            Outcome::Refunded => DisputeStatus::Refunded,
+        }
+    }
+}
+
+/// A dispute over the funds of a single period.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dispute {
+    pub period: u32,
+    pub opened_by: Address,
+    pub opened_at: u64,
+    /// When anyone can apply the fallback if the arbiters have not ruled.
+    pub deadline: u64,
+    /// Hash of the evidence, linking the dispute to its record off-chain.
+    pub evidence: BytesN<32>,
+    pub status: DisputeStatus,
+    /// The outcome each arbiter currently votes for.
+    pub votes: Map<Address, Outcome>,
+    /// When the dispute was resolved, if it has been.
+    pub resolved_at: Option<u64>,
+    /// What the resolution paid to the landlord.
+    pub to_landlord: i128,
+    /// What the resolution refunded to the tenants.
+    pub to_tenants: i128,
+}